@@ -0,0 +1,107 @@
+//! Procedural macros for [simple_txtar](https://docs.rs/simple_txtar).
+//!
+//! These are re-exported by `simple_txtar` when its `macros` feature is enabled and should be
+//! used from there rather than by depending on this crate directly.
+#![warn(missing_docs, missing_debug_implementations)]
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, Span};
+use quote::quote;
+use std::{env, fs, path::PathBuf};
+use syn::{parse_macro_input, LitStr};
+
+// The file marker scanner and fixture validation are shared with simple_txtar itself so that
+// archives are parsed and checked in exactly the same way by both crates.
+use simple_txtar_scan as scan;
+
+/// Parse a `txtar` file at compile time, expanding to a `simple_txtar::StaticArchive`.
+///
+/// See the documentation of `simple_txtar::include_txtar` for details.
+#[proc_macro]
+pub fn include_txtar(input: TokenStream) -> TokenStream {
+    let lit = parse_macro_input!(input as LitStr);
+
+    match expand(&lit) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(lit: &LitStr) -> syn::Result<proc_macro2::TokenStream> {
+    let root = env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from);
+    let path = match root {
+        Some(root) => root.join(lit.value()),
+        None => PathBuf::from(lit.value()),
+    };
+    let s = fs::read_to_string(&path)
+        .map_err(|e| syn::Error::new(lit.span(), format!("unable to read {path:?}: {e}")))?;
+
+    let problems = scan::validate(&s);
+    if !problems.is_empty() {
+        let msg = problems
+            .iter()
+            .map(|(line, problem)| format!("{}:{line}: {problem}", path.display()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        return Err(syn::Error::new(
+            lit.span(),
+            format!("invalid txtar file\n{msg}"),
+        ));
+    }
+
+    let (comment, files) = parse(&s);
+    let comment = Literal::string(&comment);
+    let files = files.iter().map(|(name, content)| {
+        let name = Literal::string(name);
+        let content = Literal::string(content);
+        quote!(::simple_txtar::StaticFile::new(#name, #content))
+    });
+    // Including the file marks it as a dependency of the calling crate so that changes to it
+    // trigger a rebuild.
+    let tracked = LitStr::new(&path.to_string_lossy(), Span::call_site());
+
+    Ok(quote!({
+        const _: &str = ::core::include_str!(#tracked);
+        ::simple_txtar::StaticArchive::new(#comment, &[#(#files),*])
+    }))
+}
+
+/// Parse `s` in the same way as `simple_txtar::Archive::from`, returning the comment along with
+/// the name and content of each file.
+fn parse(s: &str) -> (String, Vec<(String, String)>) {
+    let (comment, segments) = scan::split_segments(scan::strip_bom(s), false);
+    let files = segments
+        .map(|(marker, content)| (marker.name.trim().to_string(), fixed(content)))
+        .collect();
+
+    (fixed(comment), files)
+}
+
+fn fixed(s: &str) -> String {
+    let mut s = s.to_string();
+    if scan::missing_newline(&s) {
+        s.push('\n');
+    }
+
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_matches_txtar_format() {
+        let (comment, files) =
+            parse("\u{feff}comment\n-- a --\nfoo\n--  b c  --\r\n-- d --\nno newline");
+
+        assert_eq!(comment, "comment\n");
+        assert_eq!(
+            files,
+            vec![
+                ("a".to_string(), "foo\n".to_string()),
+                ("b c".to_string(), String::new()),
+                ("d".to_string(), "no newline\n".to_string()),
+            ]
+        );
+    }
+}
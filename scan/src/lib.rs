@@ -0,0 +1,349 @@
+//! Scanning of file markers and validation of archives used as test fixtures.
+//!
+//! This is shared by [simple_txtar](https://docs.rs/simple_txtar) and its `include_txtar!` macro
+//! so that both find files and reject fixtures in exactly the same way. It is an implementation
+//! detail of those crates and should not be depended on directly.
+#![warn(missing_docs, missing_debug_implementations)]
+use std::{
+    collections::HashSet,
+    iter::Peekable,
+    path::{Component, Path},
+};
+
+/// A file marker at the start of a line other than the first
+pub const NEWLINE_MARKER: &str = "\n-- ";
+/// The start of a file marker line
+pub const MARKER: &str = "-- ";
+/// The end of a file marker line
+pub const MARKER_END: &str = " --";
+/// The length of a file marker line with an empty name
+pub const MARKER_LEN: usize = MARKER.len() + MARKER_END.len();
+/// The byte order mark that is ignored at the start of an archive
+pub const BOM: char = '\u{feff}';
+
+/// Remove a leading byte order mark from `s`.
+pub fn strip_bom(s: &str) -> &str {
+    s.strip_prefix(BOM).unwrap_or(s)
+}
+
+/// Remove a leading UTF-8 byte order mark from `s`.
+pub fn strip_bom_bytes(s: &[u8]) -> &[u8] {
+    let mut buf = [0; 3];
+    s.strip_prefix(BOM.encode_utf8(&mut buf).as_bytes())
+        .unwrap_or(s)
+}
+
+/// The number of `\n` characters in `s`.
+pub fn count_newlines(s: &str) -> usize {
+    memchr::memchr_iter(b'\n', s.as_bytes()).count()
+}
+
+/// Whether the final line of `s` is missing the newline that terminates it when written out.
+pub fn missing_newline(s: impl AsRef<[u8]>) -> bool {
+    let s = s.as_ref();
+    !(s.is_empty() || s.ends_with(b"\n"))
+}
+
+/// A file marker line found in an archive.
+#[derive(Debug)]
+pub struct Marker<'a, T: ?Sized = str> {
+    /// The full marker line including its line ending
+    pub line: &'a T,
+    /// The untrimmed file name
+    pub name: &'a T,
+}
+
+impl<'a> Marker<'a, [u8]> {
+    /// Convert a marker found in the bytes of `s` back into one borrowing from `s`.
+    pub fn within(self, s: &'a str) -> Marker<'a> {
+        Marker {
+            line: sub_str(s, self.line),
+            name: sub_str(s, self.name),
+        }
+    }
+}
+
+/// The part of `s` covered by `sub`, which must have been sliced from `s.as_bytes()`. The byte
+/// level scanner only ever splits its input at ASCII bytes or around whole whitespace characters
+/// so everything it returns from valid UTF-8 input starts and ends on a char boundary.
+fn sub_str<'a>(s: &'a str, sub: &[u8]) -> &'a str {
+    let start = sub.as_ptr() as usize - s.as_ptr() as usize;
+
+    &s[start..start + sub.len()]
+}
+
+/// Split the input into the comment and an iterator over each file marker along with the content
+/// that follows it. This is a thin wrapper around [split_byte_segments] so that both text and byte
+/// archives are parsed by the same scanner.
+pub fn split_segments(s: &str, lenient: bool) -> (&str, Segments<'_>) {
+    let (comment, inner) = split_byte_segments(s.as_bytes(), lenient);
+
+    (sub_str(s, comment), Segments { s, inner })
+}
+
+/// The file markers in a text archive along with their content, see [split_segments].
+#[derive(Debug)]
+pub struct Segments<'a> {
+    s: &'a str,
+    inner: ByteSegments<'a>,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = (Marker<'a>, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (marker, content) = self.inner.next()?;
+
+        Some((marker.within(self.s), sub_str(self.s, content)))
+    }
+}
+
+/// Split the input into the comment and an iterator over each file marker along with the content
+/// that follows it. The input is scanned in a single pass, using memchr to jump between lines that
+/// could be the start of a file marker.
+pub fn split_byte_segments(s: &[u8], lenient: bool) -> (&[u8], ByteSegments<'_>) {
+    let needle: &'static [u8] = if lenient {
+        b"\n--"
+    } else {
+        NEWLINE_MARKER.as_bytes()
+    };
+    let mut markers = Markers {
+        s,
+        lenient,
+        at_start: true,
+        candidates: memchr::memmem::find_iter(s, needle),
+    }
+    .peekable();
+    let comment_end = markers.peek().map_or(s.len(), |(i, _)| *i);
+
+    (&s[..comment_end], ByteSegments { s, markers })
+}
+
+/// The file markers in a byte archive along with their content, see [split_byte_segments].
+#[derive(Debug)]
+pub struct ByteSegments<'a> {
+    s: &'a [u8],
+    markers: Peekable<Markers<'a>>,
+}
+
+impl<'a> Iterator for ByteSegments<'a> {
+    type Item = (Marker<'a, [u8]>, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, marker) = self.markers.next()?;
+        let content_start = start + marker.line.len();
+        let content_end = self.markers.peek().map_or(self.s.len(), |(i, _)| *i);
+
+        Some((marker, &self.s[content_start..content_end]))
+    }
+}
+
+/// The byte offset and contents of each valid file marker line within the input.
+#[derive(Debug)]
+struct Markers<'a> {
+    s: &'a [u8],
+    lenient: bool,
+    at_start: bool,
+    candidates: memchr::memmem::FindIter<'a, 'static>,
+}
+
+impl<'a> Iterator for Markers<'a> {
+    type Item = (usize, Marker<'a, [u8]>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if std::mem::take(&mut self.at_start) {
+            if let Some(marker) = try_parse_marker(self.s, self.lenient) {
+                return Some((0, marker));
+            }
+        }
+
+        self.candidates.by_ref().find_map(|i| {
+            let i = i + 1; // skip the newline preceding the marker
+            try_parse_marker(&self.s[i..], self.lenient).map(|marker| (i, marker))
+        })
+    }
+}
+
+/// The untrimmed file name from the given line if it is a valid file marker.
+pub fn marker_name(line: &str) -> Option<&str> {
+    try_parse_marker(line.as_bytes(), false).map(|m| sub_str(line, m.name))
+}
+
+/// Whether the given line (with or without its line ending) is a valid file marker.
+pub fn is_marker(line: &str) -> bool {
+    try_parse_marker(line.as_bytes(), false).is_some()
+}
+
+/// A line that starts and ends with "--" around something other than dashes and whitespace
+/// but which was not accepted by try_parse_marker.
+pub fn looks_like_marker(line: &[u8]) -> bool {
+    match line.strip_prefix(b"--").and_then(|s| s.strip_suffix(b"--")) {
+        Some(inner) => inner.utf8_chunks().any(|chunk| {
+            !chunk.invalid().is_empty()
+                || chunk
+                    .valid()
+                    .contains(|c: char| !(c == '-' || c.is_whitespace()))
+        }),
+        None => false,
+    }
+}
+
+/// Trim leading and trailing whitespace from a file name that may not be valid UTF-8, giving the
+/// same result as str::trim for names that are.
+pub fn trim_name(name: &[u8]) -> &[u8] {
+    let leading = name.utf8_chunks().next().map_or(0, |chunk| {
+        chunk.valid().len() - chunk.valid().trim_start().len()
+    });
+    let name = &name[leading..];
+    let trailing = match name.utf8_chunks().last() {
+        Some(chunk) if chunk.invalid().is_empty() => {
+            chunk.valid().len() - chunk.valid().trim_end().len()
+        }
+        _ => 0,
+    };
+
+    &name[..name.len() - trailing]
+}
+
+/// Parse the file marker at the start of `input`, also accepting near misses such as `-- name--`
+/// when `lenient` is set.
+pub fn try_parse_marker(input: &[u8], lenient: bool) -> Option<Marker<'_, [u8]>> {
+    if !input.starts_with(b"--") {
+        return None;
+    }
+
+    let line = match memchr::memchr(b'\n', input) {
+        Some(i) => &input[..=i], // include the newline in the line
+        None => input,
+    };
+    let s = line.strip_suffix(b"\n").unwrap_or(line);
+    let s = s.strip_suffix(b"\r").unwrap_or(s); // tolerate CRLF line endings
+
+    if !(s.starts_with(MARKER.as_bytes())
+        && s.ends_with(MARKER_END.as_bytes())
+        && s.len() >= MARKER_LEN)
+    {
+        if lenient && looks_like_marker(s) {
+            let start = s.iter().position(|&b| b != b'-').unwrap_or(s.len());
+            let end = s.iter().rposition(|&b| b != b'-').map_or(start, |i| i + 1);
+            let name = trim_name(&s[start..end]);
+            return Some(Marker { line, name });
+        }
+
+        return None;
+    }
+
+    let name = &s[MARKER.len()..s.len() - MARKER_END.len()];
+
+    Some(Marker { line, name })
+}
+
+/// A component of a file name that would place the file outside of the directory it is extracted
+/// to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathProblem {
+    /// The name is an absolute path
+    Absolute,
+    /// The name contains a `..` component
+    ParentDir,
+}
+
+/// The first component of `name` that would place a file outside of the directory it is extracted
+/// to, if any.
+pub fn path_problem(name: &str) -> Option<PathProblem> {
+    Path::new(name).components().find_map(|c| match c {
+        Component::RootDir | Component::Prefix(_) => Some(PathProblem::Absolute),
+        Component::ParentDir => Some(PathProblem::ParentDir),
+        _ => None,
+    })
+}
+
+/// Check an archive that is being embedded as a test fixture, returning the 1-based line number
+/// and a description of each problem found in line order. Fixtures are rejected if they contain
+/// duplicate, empty or absolute file names, file names with `..` components or are not in the
+/// canonical form that formatting the parsed archive would produce.
+pub fn validate(raw: &str) -> Vec<(usize, String)> {
+    let (comment, segments) = split_segments(strip_bom(raw), false);
+    let mut problems = Vec::new();
+    let mut seen = HashSet::new();
+    let mut line = 1 + count_newlines(comment);
+    let mut canonical = String::with_capacity(raw.len());
+    push_with_trailing_newline(&mut canonical, comment);
+
+    for (marker, content) in segments {
+        let name = marker.name.trim();
+        let mut push = |problem: &str| problems.push((line, problem.to_string()));
+
+        if !seen.insert(name) {
+            push("duplicate file name");
+        }
+        if name.is_empty() {
+            push("empty file name");
+        }
+        match path_problem(name) {
+            Some(PathProblem::Absolute) => push("file name is an absolute path"),
+            Some(PathProblem::ParentDir) => push("file name contains a '..' component"),
+            None => (),
+        }
+
+        canonical.push_str(MARKER);
+        canonical.push_str(name);
+        canonical.push_str(MARKER_END);
+        canonical.push('\n');
+        push_with_trailing_newline(&mut canonical, content);
+        line += 1 + count_newlines(content);
+    }
+
+    if let Some(line) = first_difference(raw, &canonical) {
+        problems.push((
+            line,
+            "not in canonical form (check for a byte order mark, extra whitespace around file \
+             names, CRLF file markers or a missing trailing newline)"
+                .to_string(),
+        ));
+    }
+
+    problems.sort_by_key(|(line, _)| *line);
+    problems
+}
+
+fn push_with_trailing_newline(buf: &mut String, s: &str) {
+    buf.push_str(s);
+    if missing_newline(s) {
+        buf.push('\n');
+    }
+}
+
+/// The 1-based number of the first line that differs between `a` and `b`.
+fn first_difference(a: &str, b: &str) -> Option<usize> {
+    let mut a_lines = a.split_inclusive('\n');
+    let mut b_lines = b.split_inclusive('\n');
+    let mut line = 1;
+    loop {
+        match (a_lines.next(), b_lines.next()) {
+            (None, None) => return None,
+            (a, b) if a != b => return Some(line),
+            _ => line += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn problems_are_reported_by_line() {
+        let s = "comment\n-- a --\n-- ../b --\n-- a --\n--  c --\nfoo\n-- /d --\n";
+        let lines: Vec<usize> = validate(s).into_iter().map(|(line, _)| line).collect();
+
+        assert_eq!(lines, vec![3, 4, 5, 7]);
+        assert!(validate("-- a --\nfoo\n").is_empty());
+    }
+
+    #[test]
+    fn non_utf8_names_are_trimmed_like_str() {
+        assert_eq!(trim_name("\u{3000} a b\u{a0}".as_bytes()), b"a b");
+        assert_eq!(trim_name(b" \xff a \xfe "), b"\xff a \xfe");
+    }
+}
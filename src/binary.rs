@@ -0,0 +1,95 @@
+//! Handling files that are not valid UTF-8 when building an archive from other sources.
+use crate::File;
+use std::{io, path::Path};
+
+/// How files that are not valid UTF-8 are handled when reading a directory using
+/// [Archive::from_dir_with][crate::Archive::from_dir_with], or another archive format into an
+/// [Archive][crate::Archive].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binary {
+    /// Return an error of kind [std::io::ErrorKind::InvalidData]
+    #[default]
+    Error,
+    /// Leave the file out of the archive, recording it in the
+    /// [PackReport][crate::PackReport] with [SkipReason::Binary][crate::SkipReason::Binary]
+    Skip,
+    /// Store the content of the file as base64, wrapped at 76 columns, under its name with a
+    /// `.base64` suffix added.
+    ///
+    /// Files stored in this way can be restored by enabling
+    /// [ExtractOptions::decode_base64][crate::ExtractOptions::decode_base64].
+    #[cfg(feature = "base64")]
+    Base64,
+}
+
+impl Binary {
+    /// Build a [File] from raw content according to this policy, returning `None` if the file
+    /// should be skipped. `path` is only used to describe the file in errors.
+    pub(crate) fn file(
+        self,
+        name: String,
+        bytes: Vec<u8>,
+        path: &Path,
+    ) -> io::Result<Option<File>> {
+        match String::from_utf8(bytes) {
+            Ok(content) => Ok(Some(File::new(name, content))),
+            #[cfg_attr(not(feature = "base64"), allow(unused_variables))]
+            Err(e) => match self {
+                Self::Error => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("file is not valid UTF-8: {path:?}"),
+                )),
+                Self::Skip => Ok(None),
+                #[cfg(feature = "base64")]
+                Self::Base64 => Ok(Some(File::new(
+                    format!("{name}{BASE64_SUFFIX}"),
+                    encode(e.as_bytes()),
+                ))),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "base64")]
+pub(crate) use encoding::*;
+
+#[cfg(feature = "base64")]
+mod encoding {
+    use base64::{engine::general_purpose::STANDARD, DecodeError, Engine};
+
+    /// The suffix added to the names of files stored using [Binary::Base64][super::Binary].
+    pub(crate) const BASE64_SUFFIX: &str = ".base64";
+
+    const LINE_LEN: usize = 76;
+
+    pub(crate) fn encode(bytes: &[u8]) -> String {
+        let encoded = STANDARD.encode(bytes);
+        let mut s = String::with_capacity(encoded.len() + encoded.len() / LINE_LEN + 1);
+        for line in encoded.as_bytes().chunks(LINE_LEN) {
+            // base64 output is always ASCII
+            s.push_str(std::str::from_utf8(line).unwrap());
+            s.push('\n');
+        }
+
+        s
+    }
+
+    pub(crate) fn decode(s: &str) -> Result<Vec<u8>, DecodeError> {
+        let s: String = s.split_ascii_whitespace().collect();
+        STANDARD.decode(s)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn encoding_round_trips_and_wraps() {
+            let bytes: Vec<u8> = (0..=255).collect();
+            let s = encode(&bytes);
+
+            assert!(s.lines().all(|l| l.len() <= LINE_LEN));
+            assert_eq!(decode(&s).unwrap(), bytes);
+        }
+    }
+}
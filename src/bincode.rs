@@ -0,0 +1,69 @@
+//! A compact binary encoding of [Archive]s for caching parsed fixtures.
+use crate::Archive;
+use bincode::{config::standard, error::DecodeError};
+
+impl Archive {
+    /// Encode this archive using [bincode].
+    ///
+    /// The encoded form contains the comment and the name and content of each file, in the same
+    /// way as the [serde][crate::serde] representation, and can be decoded far faster than the
+    /// original `txtar` text can be parsed. This makes it useful for caching large fixture sets
+    /// that have been prepared ahead of time, for example by a build script. The encoding is not
+    /// guaranteed to be stable between versions of this crate so it should not be used for long
+    /// term storage.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("comment\n-- a.txt --\nfoo\n");
+    /// let bytes = a.to_bincode();
+    ///
+    /// assert_eq!(Archive::from_bincode(&bytes).unwrap(), a);
+    /// ```
+    pub fn to_bincode(&self) -> Vec<u8> {
+        bincode::serde::encode_to_vec(self, standard())
+            .expect("encoding an archive to a vec is infallible")
+    }
+
+    /// Decode an archive that was encoded using [Archive::to_bincode].
+    ///
+    /// This will error if the input is not a valid encoding of an archive, including if there is
+    /// any trailing data after the encoded archive.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// // Written ahead of time using `Archive::to_bincode`
+    /// let bytes = std::fs::read("target/corpus.bin").unwrap();
+    /// let a = Archive::from_bincode(&bytes).unwrap();
+    /// ```
+    pub fn from_bincode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let (a, n) = bincode::serde::decode_from_slice(bytes, standard())?;
+        if n != bytes.len() {
+            return Err(DecodeError::OtherString(format!(
+                "{} bytes of trailing data after archive",
+                bytes.len() - n
+            )));
+        }
+
+        Ok(a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bincode_round_trips_and_rejects_trailing_data() {
+        let a = Archive::from("comment\n-- a --\n\u{1f600}\n-- b/c --\n-- a --\nagain");
+        let mut bytes = a.to_bincode();
+
+        assert_eq!(Archive::from_bincode(&bytes).unwrap(), a);
+        bytes.push(0);
+        assert!(Archive::from_bincode(&bytes).is_err());
+        assert!(Archive::from_bincode(&bytes[..bytes.len() / 2]).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+//! A zero-copy view of a `txtar` archive that borrows from its input.
+use crate::{
+    scan::{split_segments, strip_bom},
+    Archive, File,
+};
+use std::{borrow::Cow, fmt, ops::Index, slice::Iter};
+
+/// A `txtar` archive that borrows its comment, file names and file contents from the text it was
+/// parsed from.
+///
+/// Parsing an [ArchiveRef] does not copy any content unless a segment of the input is missing its
+/// trailing newline, which can only happen for the final segment. This makes it well suited to
+/// inspecting fixtures embedded with [include_str] without allocating a copy of each file.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::{Archive, ArchiveRef};
+///
+/// let s = "comment\n-- a.txt --\nfoo\n-- b.txt --\nbar";
+/// let a = ArchiveRef::from(s);
+///
+/// assert_eq!(a.comment(), "comment\n");
+/// assert_eq!(a["a.txt"].content, "foo\n");
+/// assert_eq!(a.to_archive(), Archive::from(s));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ArchiveRef<'a> {
+    comment: Cow<'a, str>,
+    files: Vec<FileRef<'a>>,
+}
+
+impl<'a> ArchiveRef<'a> {
+    /// The optional comment at the top of the `txtar` archive.
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// Attempt to get a [FileRef] from the archive by name, returning the first match.
+    pub fn get(&self, filename: &str) -> Option<&FileRef<'a>> {
+        self.files.iter().find(|f| f.name == filename)
+    }
+
+    /// Iterate over the [FileRef]s contained in this archive in the order they were specified in
+    /// the original `txtar` file.
+    pub fn iter(&self) -> Iter<'_, FileRef<'a>> {
+        self.files.iter()
+    }
+
+    /// The number of files in the archive.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether or not the archive contains any files.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Copy the contents of this archive into an owned [Archive].
+    ///
+    /// The resulting files do not have the source positions that are recorded when parsing an
+    /// [Archive] directly, so methods such as [Archive::marker_line] will return `None`.
+    pub fn to_archive(&self) -> Archive {
+        Archive {
+            comment: self.comment.to_string(),
+            files: self.files.iter().map(File::from).collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for ArchiveRef<'a> {
+    fn from(s: &'a str) -> Self {
+        let (comment, segments) = split_segments(strip_bom(s), false);
+
+        Self {
+            comment: fix_trailing_newline(comment),
+            files: segments
+                .map(|(marker, content)| FileRef {
+                    name: marker.name.trim(),
+                    content: fix_trailing_newline(content),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a> From<ArchiveRef<'a>> for Archive {
+    fn from(a: ArchiveRef<'a>) -> Self {
+        Self {
+            comment: a.comment.into_owned(),
+            files: a.files.into_iter().map(File::from).collect(),
+        }
+    }
+}
+
+impl<'a> Index<usize> for ArchiveRef<'a> {
+    type Output = FileRef<'a>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.files[index]
+    }
+}
+
+impl<'a> Index<&str> for ArchiveRef<'a> {
+    type Output = FileRef<'a>;
+
+    fn index(&self, filename: &str) -> &Self::Output {
+        self.get(filename).unwrap()
+    }
+}
+
+impl<'a> IntoIterator for ArchiveRef<'a> {
+    type Item = FileRef<'a>;
+    type IntoIter = std::vec::IntoIter<FileRef<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.into_iter()
+    }
+}
+
+impl<'a, 'b> IntoIterator for &'b ArchiveRef<'a> {
+    type Item = &'b FileRef<'a>;
+    type IntoIter = Iter<'b, FileRef<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.iter()
+    }
+}
+
+impl fmt::Display for ArchiveRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.comment)?;
+        for file in self.files.iter() {
+            write!(f, "{file}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A single file within an [ArchiveRef].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FileRef<'a> {
+    /// The name of the file
+    pub name: &'a str,
+    /// The content of the file, borrowed from the input unless a trailing newline was added
+    pub content: Cow<'a, str>,
+}
+
+impl<'a> From<FileRef<'a>> for File {
+    fn from(f: FileRef<'a>) -> Self {
+        File::new(f.name, f.content)
+    }
+}
+
+impl From<&FileRef<'_>> for File {
+    fn from(f: &FileRef<'_>) -> Self {
+        File::new(f.name, f.content.as_ref())
+    }
+}
+
+impl fmt::Display for FileRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "-- {} --", self.name)?;
+        write!(f, "{}", self.content)
+    }
+}
+
+fn fix_trailing_newline(s: &str) -> Cow<'_, str> {
+    if s.is_empty() || s.ends_with('\n') {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(format!("{s}\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_final_segment_is_copied() {
+        let s = "\u{feff}comment\n-- a --\nfoo\n--  b  --\nbar";
+        let a = ArchiveRef::from(s);
+
+        assert!(matches!(a.comment, Cow::Borrowed("comment\n")));
+        assert!(matches!(a[0].content, Cow::Borrowed("foo\n")));
+        assert!(matches!(a[1].content, Cow::Owned(_)));
+        assert_eq!(a[1].name, "b");
+        assert_eq!(a.to_string(), Archive::from(s).to_string());
+        assert_eq!(Archive::from(a), Archive::from(s));
+    }
+}
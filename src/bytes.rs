@@ -0,0 +1,453 @@
+//! A byte-oriented variant of [Archive] for content that is not valid UTF-8.
+use crate::{
+    fix_trailing_newline_bytes,
+    scan::{split_byte_segments, strip_bom_bytes, trim_name, ByteSegments},
+    write_with_trailing_newline, Archive, File, MARKER, MARKER_END,
+};
+use std::{
+    borrow::Cow,
+    ffi::OsStr,
+    fmt, fs,
+    io::{self, Write},
+    ops::Index,
+    path::Path,
+    slice::{Iter, IterMut},
+    str::{self, Utf8Error},
+};
+
+/// A `txtar` archive whose comment, file names and file contents are arbitrary bytes.
+///
+/// Storing binary data is a non-goal of the `txtar` format but real world archives sometimes
+/// contain text in other encodings such as latin-1. A [ByteArchive] is parsed using the same file
+/// marker rules as an [Archive] without requiring the input to be valid UTF-8, leaving any
+/// decoding of the contents to the caller.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::ByteArchive;
+///
+/// let a = ByteArchive::from(&b"-- latin1.txt --\ncaf\xe9\n"[..]);
+///
+/// assert_eq!(a[0].name, b"latin1.txt");
+/// assert_eq!(a[0].content, b"caf\xe9\n");
+/// assert!(a.to_archive().is_err());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ByteArchive {
+    comment: Vec<u8>,
+    files: Vec<ByteFile>,
+}
+
+impl ByteArchive {
+    /// Parse a `txtar` archive from the file at the specified path without requiring its
+    /// contents to be valid UTF-8.
+    ///
+    /// This will error if there are any issues with reading the file.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::ByteArchive;
+    ///
+    /// let res = ByteArchive::from_file("my_txtar_archive");
+    /// ```
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let raw = fs::read(path)?;
+
+        Ok(Self::from(raw.as_slice()))
+    }
+
+    /// Serialize this archive as a `txtar` file at the specified path, creating the file if it
+    /// does not exist and truncating it if it does.
+    pub fn to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut w = io::BufWriter::new(fs::File::create(path)?);
+        self.to_writer(&mut w)?;
+
+        w.flush()
+    }
+
+    /// Serialize this archive to the given writer, inserting any missing trailing newlines in the
+    /// same way as [Archive::to_writer].
+    pub fn to_writer(&self, mut w: impl Write) -> io::Result<()> {
+        write_with_trailing_newline(&mut w, &self.comment)?;
+        for file in self.files.iter() {
+            w.write_all(MARKER.as_bytes())?;
+            w.write_all(&file.name)?;
+            w.write_all(MARKER_END.as_bytes())?;
+            w.write_all(b"\n")?;
+            write_with_trailing_newline(&mut w, &file.content)?;
+        }
+
+        Ok(())
+    }
+
+    /// Serialize this archive to a `Vec<u8>`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::ByteArchive;
+    ///
+    /// let a = ByteArchive::from(&b"comment\n-- a --\n\xff"[..]);
+    ///
+    /// assert_eq!(a.to_bytes(), b"comment\n-- a --\n\xff\n");
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.to_writer(&mut buf)
+            .expect("writing to a Vec<u8> is infallible");
+
+        buf
+    }
+
+    /// Convert this archive into an [Archive], returning an error if the comment or any file
+    /// name or content is not valid UTF-8.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, ByteArchive};
+    ///
+    /// let a = ByteArchive::from(&b"-- a --\nfoo\n"[..]);
+    ///
+    /// assert_eq!(a.to_archive(), Ok(Archive::from("-- a --\nfoo\n")));
+    /// ```
+    pub fn to_archive(&self) -> Result<Archive, Utf8Error> {
+        let files = self
+            .files
+            .iter()
+            .map(|f| {
+                Ok(File::new(
+                    str::from_utf8(&f.name)?,
+                    str::from_utf8(&f.content)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, Utf8Error>>()?;
+
+        Ok(Archive {
+            comment: str::from_utf8(&self.comment)?.to_string(),
+            files: files.into(),
+        })
+    }
+
+    /// Convert this archive into an [Archive], replacing any invalid UTF-8 with
+    /// [U+FFFD REPLACEMENT CHARACTER][char::REPLACEMENT_CHARACTER].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::ByteArchive;
+    ///
+    /// let a = ByteArchive::from(&b"-- a --\ncaf\xe9\n"[..]).to_archive_lossy();
+    ///
+    /// assert_eq!(a["a"].content, "caf\u{fffd}\n");
+    /// ```
+    pub fn to_archive_lossy(&self) -> Archive {
+        Archive {
+            comment: String::from_utf8_lossy(&self.comment).into_owned(),
+            files: self
+                .files
+                .iter()
+                .map(|f| {
+                    File::new(
+                        String::from_utf8_lossy(&f.name),
+                        String::from_utf8_lossy(&f.content),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// The comment section of the archive.
+    pub fn comment(&self) -> &[u8] {
+        &self.comment
+    }
+
+    /// A mutable reference to the comment section of the archive.
+    pub fn comment_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.comment
+    }
+
+    /// Attempt to get a [ByteFile] from the archive by name, returning the first match.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::ByteArchive;
+    ///
+    /// let a = ByteArchive::from(&b"-- caf\xe9 --\nfoo\n"[..]);
+    ///
+    /// assert_eq!(a.get(b"caf\xe9").map(|f| &f.content[..]), Some(&b"foo\n"[..]));
+    /// assert!(a.get("bar").is_none());
+    /// ```
+    pub fn get(&self, name: impl AsRef<[u8]>) -> Option<&ByteFile> {
+        let name = name.as_ref();
+        self.files.iter().find(|f| f.name == name)
+    }
+
+    /// Attempt to get a mutable reference to a [ByteFile] from the archive by name, returning
+    /// the first match.
+    pub fn get_mut(&mut self, name: impl AsRef<[u8]>) -> Option<&mut ByteFile> {
+        let name = name.as_ref();
+        self.files.iter_mut().find(|f| f.name == name)
+    }
+
+    /// Iterate over the [ByteFile]s contained in this archive in order.
+    pub fn iter(&self) -> Iter<'_, ByteFile> {
+        self.files.iter()
+    }
+
+    /// Iterate mutably over the [ByteFile]s contained in this archive in order.
+    pub fn iter_mut(&mut self) -> IterMut<'_, ByteFile> {
+        self.files.iter_mut()
+    }
+
+    /// Add a new [ByteFile] to the end of the archive.
+    pub fn add_file(&mut self, file: impl Into<ByteFile>) {
+        self.files.push(file.into());
+    }
+
+    /// The number of files in the archive.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether or not the archive contains any files.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl Index<usize> for ByteArchive {
+    type Output = ByteFile;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.files[index]
+    }
+}
+
+impl IntoIterator for ByteArchive {
+    type Item = ByteFile;
+    type IntoIter = std::vec::IntoIter<ByteFile>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ByteArchive {
+    type Item = &'a ByteFile;
+    type IntoIter = Iter<'a, ByteFile>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.iter()
+    }
+}
+
+impl From<&[u8]> for ByteArchive {
+    fn from(s: &[u8]) -> Self {
+        let (comment, segments) = split_byte_segments(strip_bom_bytes(s), false);
+
+        Self {
+            comment: fix_trailing_newline_bytes(comment),
+            files: segments
+                .map(|(marker, content)| ByteFile {
+                    name: trim_name(marker.name).to_vec(),
+                    content: fix_trailing_newline_bytes(content),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<Vec<u8>> for ByteArchive {
+    fn from(s: Vec<u8>) -> Self {
+        Self::from(s.as_slice())
+    }
+}
+
+impl From<&Archive> for ByteArchive {
+    fn from(a: &Archive) -> Self {
+        Self {
+            comment: a.comment_without_bom().as_bytes().to_vec(),
+            files: a.files.iter().map(ByteFile::from).collect(),
+        }
+    }
+}
+
+impl From<Archive> for ByteArchive {
+    fn from(a: Archive) -> Self {
+        Self::from(&a)
+    }
+}
+
+/// A single file within a [ByteArchive].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct ByteFile {
+    /// The name of the file as it appears in its file marker
+    pub name: Vec<u8>,
+    /// The raw content of the file
+    pub content: Vec<u8>,
+}
+
+impl ByteFile {
+    /// Create a new [ByteFile] with the given name and content.
+    pub fn new(name: impl Into<Vec<u8>>, content: impl Into<Vec<u8>>) -> Self {
+        Self {
+            name: name.into(),
+            content: content.into(),
+        }
+    }
+
+    /// Create a new [ByteFile] named using the given path, which does not need to be valid
+    /// UTF-8.
+    ///
+    /// On Unix platforms the name is the raw bytes of the path. On other platforms the name is
+    /// the platform specific encoding used by [OsStr::as_encoded_bytes].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::ByteFile;
+    ///
+    /// let f = ByteFile::from_path("a/b.txt", "foo\n");
+    ///
+    /// assert_eq!(f.name, b"a/b.txt");
+    /// ```
+    pub fn from_path(path: impl AsRef<Path>, content: impl Into<Vec<u8>>) -> Self {
+        Self::new(path.as_ref().as_os_str().as_encoded_bytes(), content)
+    }
+
+    /// The name of this file as an [OsStr].
+    ///
+    /// On Unix platforms this is the raw bytes of the name without any conversion. On other
+    /// platforms names that are not valid UTF-8 have any invalid sequences replaced with
+    /// [U+FFFD REPLACEMENT CHARACTER][char::REPLACEMENT_CHARACTER].
+    pub fn os_name(&self) -> Cow<'_, OsStr> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            Cow::Borrowed(OsStr::from_bytes(&self.name))
+        }
+
+        #[cfg(not(unix))]
+        match String::from_utf8_lossy(&self.name) {
+            Cow::Borrowed(s) => Cow::Borrowed(OsStr::new(s)),
+            Cow::Owned(s) => Cow::Owned(s.into()),
+        }
+    }
+
+    /// The name of this file as a [Path], converted as described for [ByteFile::os_name].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::ByteFile;
+    /// use std::path::Path;
+    ///
+    /// let f = ByteFile::new("a/b.txt", "foo\n");
+    ///
+    /// assert_eq!(f.path(), Path::new("a/b.txt"));
+    /// ```
+    pub fn path(&self) -> Cow<'_, Path> {
+        match self.os_name() {
+            Cow::Borrowed(s) => Cow::Borrowed(Path::new(s)),
+            Cow::Owned(s) => Cow::Owned(s.into()),
+        }
+    }
+}
+
+impl From<&File> for ByteFile {
+    fn from(f: &File) -> Self {
+        Self::new(f.name.as_bytes(), f.content.as_bytes())
+    }
+}
+
+impl From<File> for ByteFile {
+    fn from(f: File) -> Self {
+        Self::new(f.name, f.content)
+    }
+}
+
+impl<T, U> From<(T, U)> for ByteFile
+where
+    T: Into<Vec<u8>>,
+    U: Into<Vec<u8>>,
+{
+    fn from((name, content): (T, U)) -> Self {
+        Self::new(name, content)
+    }
+}
+
+/// Lazily iterate over the files in a `txtar` archive held as bytes, validating only that each
+/// file name is UTF-8.
+///
+/// This is the byte oriented equivalent of [parse_iter][crate::parse_iter]: file markers are
+/// located directly in the input and each item is the name and raw content of a file, borrowed
+/// from the input. Content is never checked, so callers with large trusted inputs only pay for
+/// validating the contents they actually use, if at all. Names that are not valid UTF-8 are
+/// returned as an error without stopping iteration.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::parse_bytes_iter;
+///
+/// let s = b"-- a.txt --\nfoo\n-- b.bin --\n\xff\xfe\n-- \xff --\n";
+/// let mut it = parse_bytes_iter(s);
+///
+/// assert_eq!(it.next(), Some(Ok(("a.txt", &b"foo\n"[..]))));
+/// assert_eq!(it.next(), Some(Ok(("b.bin", &b"\xff\xfe\n"[..]))));
+/// assert!(it.next().unwrap().is_err());
+/// assert_eq!(it.next(), None);
+/// ```
+pub fn parse_bytes_iter(s: &[u8]) -> ParseBytesIter<'_> {
+    let (_, segments) = split_byte_segments(strip_bom_bytes(s), false);
+
+    ParseBytesIter { segments }
+}
+
+/// An iterator over the files in a `txtar` archive held as bytes, created by [parse_bytes_iter].
+pub struct ParseBytesIter<'a> {
+    segments: ByteSegments<'a>,
+}
+
+impl<'a> Iterator for ParseBytesIter<'a> {
+    type Item = Result<(&'a str, &'a [u8]), Utf8Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (marker, content) = self.segments.next()?;
+
+        Some(str::from_utf8(trim_name(marker.name)).map(|name| (name, content)))
+    }
+}
+
+impl fmt::Debug for ParseBytesIter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParseBytesIter").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_archive_matches_archive_for_utf8_input() {
+        let s = "\u{feff}comment\n-- a --\r\nfoo\n--  b  --\n-- c--\nbar\n-- \u{3000}d\u{a0} --";
+        let a = ByteArchive::from(s.as_bytes());
+
+        assert_eq!(a.to_archive(), Ok(Archive::from(s)));
+        assert_eq!(a.to_bytes(), Archive::from(s).to_string().as_bytes());
+    }
+
+    #[test]
+    fn non_utf8_round_trips() {
+        let s = b"\xfe\n-- \xe9 --\n\xff\xfe\n";
+        let a = ByteArchive::from(&s[..]);
+
+        assert_eq!(a.comment(), b"\xfe\n");
+        assert_eq!(a[0], ByteFile::new(&b"\xe9"[..], &b"\xff\xfe\n"[..]));
+        assert_eq!(a.to_bytes(), s);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_names_round_trip_through_paths() {
+        let f = ByteFile::new(&b"caf\xe9.txt"[..], "");
+
+        assert_eq!(ByteFile::from_path(f.path(), ""), f);
+    }
+}
@@ -0,0 +1,110 @@
+//! A process-wide cache of archives parsed from files.
+use crate::Archive;
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, OnceLock},
+    time::SystemTime,
+};
+
+type Cache = Mutex<HashMap<PathBuf, Cached>>;
+
+struct Cached {
+    modified: SystemTime,
+    len: u64,
+    archive: Arc<Archive>,
+}
+
+// A panic while holding the lock can not leave the map in an inconsistent state so poisoning
+// is ignored.
+fn lock() -> MutexGuard<'static, HashMap<PathBuf, Cached>> {
+    static CACHE: OnceLock<Cache> = OnceLock::new();
+    CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+impl Archive {
+    /// Parse a `txtar` archive from the file at the specified path, reusing the result of any
+    /// previous call for the same path if the file has not changed since.
+    ///
+    /// Parsed archives are held in a cache shared by the whole process and are returned behind
+    /// an [Arc] so that handing them out does not copy any file contents. A cached archive is
+    /// used as long as the modification time and length of the file are the same as when it was
+    /// parsed, otherwise the file is read and parsed again. Use [Archive::clear_file_cache] to
+    /// release the memory held by the cache.
+    ///
+    /// This is intended for test binaries that load the same fixture archives from many tests,
+    /// which otherwise re-read and re-parse the file each time. As with [Archive::from_file],
+    /// this will error if there are any issues with reading the file.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    /// use std::sync::Arc;
+    ///
+    /// let a = Archive::from_file_cached("testdata/fixtures.txtar").unwrap();
+    /// let b = Archive::from_file_cached("testdata/fixtures.txtar").unwrap();
+    ///
+    /// assert!(Arc::ptr_eq(&a, &b));
+    /// ```
+    pub fn from_file_cached(path: impl AsRef<Path>) -> io::Result<Arc<Self>> {
+        let path = path.as_ref();
+        let meta = fs::metadata(path)?;
+        let (modified, len) = (meta.modified()?, meta.len());
+
+        if let Some(c) = lock().get(path) {
+            if c.modified == modified && c.len == len {
+                return Ok(c.archive.clone());
+            }
+        }
+
+        // The lock is not held while parsing so that other archives can be loaded concurrently.
+        let archive = Arc::new(Self::from(crate::read_to_string(path)?));
+        lock().insert(
+            path.to_path_buf(),
+            Cached {
+                modified,
+                len,
+                archive: archive.clone(),
+            },
+        );
+
+        Ok(archive)
+    }
+
+    /// Remove every archive from the cache used by [Archive::from_file_cached].
+    ///
+    /// Archives that have already been returned are unaffected.
+    pub fn clear_file_cache() {
+        lock().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+    use std::time::Duration;
+
+    #[test]
+    fn cache_is_invalidated_by_modification() {
+        let dir = TestDir::new("cache");
+        let path = dir.join("a.txtar");
+        Archive::from("-- a --\nfoo\n").to_file(&path).unwrap();
+
+        let a = Archive::from_file_cached(&path).unwrap();
+        let b = Archive::from_file_cached(&path).unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+
+        Archive::from("-- a --\nbar\n").to_file(&path).unwrap();
+        let f = fs::File::options().write(true).open(&path).unwrap();
+        f.set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        let c = Archive::from_file_cached(&path).unwrap();
+
+        assert_eq!(c["a"].content, "bar\n");
+    }
+}
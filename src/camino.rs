@@ -0,0 +1,64 @@
+//! Accessing the paths used by the crate as [camino] UTF-8 paths.
+//!
+//! Every filesystem API in the crate accepts [camino::Utf8Path] and [camino::Utf8PathBuf]
+//! directly as they implement `AsRef<Path>`.
+use crate::{File, FileConflict, Skipped};
+use camino::Utf8Path;
+
+impl File {
+    /// The name of this file as a relative [Utf8Path].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use camino::Utf8Path;
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("-- src/main.rs --\nfn main() {}\n");
+    /// let root = Utf8Path::new("/tmp/fixture");
+    ///
+    /// assert_eq!(root.join(a[0].utf8_path()), "/tmp/fixture/src/main.rs");
+    /// ```
+    pub fn utf8_path(&self) -> &Utf8Path {
+        Utf8Path::new(&self.name)
+    }
+}
+
+impl FileConflict {
+    /// The path of the existing file as a [Utf8Path], which is always available when the
+    /// directory passed to [Archive::check_conflicts][crate::Archive::check_conflicts] is valid
+    /// UTF-8.
+    pub fn utf8_path(&self) -> Option<&Utf8Path> {
+        Utf8Path::from_path(&self.path)
+    }
+}
+
+impl Skipped {
+    /// The path of the skipped entry as a [Utf8Path], if it is valid UTF-8.
+    pub fn utf8_path(&self) -> Option<&Utf8Path> {
+        Utf8Path::from_path(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_dir::TestDir;
+    use crate::Archive;
+    use camino::Utf8Path;
+
+    #[test]
+    fn utf8_paths_round_trip() {
+        let tmp = TestDir::new("camino");
+        let dir = Utf8Path::from_path(&tmp).unwrap();
+        let a = Archive::from("-- a/b.txt --\nfoo\n");
+
+        a.materialize(dir).unwrap();
+        let conflicts = a.check_conflicts(dir);
+        let res = Archive::from_dir(dir);
+
+        assert_eq!(res.unwrap(), a);
+        assert_eq!(
+            conflicts.unwrap()[0].utf8_path(),
+            Some(dir.join(a[0].utf8_path()).as_path())
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Writing the contents of an [Archive] into a sandboxed [cap_std] directory.
+use crate::{extract::safe_relative_path, Archive, Error, ExtractReport};
+use cap_std::fs::Dir;
+use std::{io, path::Path};
+
+impl Archive {
+    /// Write each [File][crate::File] in this archive beneath the given directory handle, using
+    /// the file names as paths relative to it.
+    ///
+    /// This behaves in the same way as [Archive::materialize], but all file system access goes
+    /// through `dir` so it is impossible for the extraction to write outside of the directory,
+    /// even if a symlink inside of it is swapped out while files are being written. Any path that
+    /// would leave the directory, including by following a symlink, results in an error.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use cap_std::{ambient_authority, fs::Dir};
+    /// use simple_txtar::Archive;
+    ///
+    /// let dir = Dir::open_ambient_dir("testdata", ambient_authority()).unwrap();
+    /// let a = Archive::from("-- a.txt --\nfoo\n-- nested/b.txt --\nbar");
+    /// a.materialize_into(&dir).unwrap();
+    /// ```
+    pub fn materialize_into(&self, dir: &Dir) -> Result<ExtractReport, Error> {
+        let paths = self
+            .files
+            .iter()
+            .map(|f| safe_relative_path(&f.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut report = ExtractReport::default();
+        for (file, path) in self.files.iter().zip(paths) {
+            create_parent_dirs(dir, path, &mut report)?;
+            dir.write(path, &file.content)?;
+            report.files_written += 1;
+            report.bytes_written += file.content.len() as u64;
+        }
+
+        Ok(report)
+    }
+}
+
+fn create_parent_dirs(dir: &Dir, path: &Path, report: &mut ExtractReport) -> io::Result<()> {
+    let missing: Vec<&Path> = path
+        .ancestors()
+        .skip(1)
+        .take_while(|p| !p.as_os_str().is_empty() && !dir.is_dir(p))
+        .collect();
+
+    for p in missing.into_iter().rev() {
+        match dir.create_dir(p) {
+            Ok(()) => report.dirs_created += 1,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && dir.is_dir(p) => (),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+    use cap_std::ambient_authority;
+    use std::fs;
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_can_not_escape_the_directory() {
+        let root = TestDir::new("cap");
+        fs::create_dir_all(root.join("sandbox")).unwrap();
+        fs::create_dir_all(root.join("outside")).unwrap();
+        std::os::unix::fs::symlink(root.join("outside"), root.join("sandbox/link")).unwrap();
+
+        let dir = Dir::open_ambient_dir(root.join("sandbox"), ambient_authority()).unwrap();
+        let ok = Archive::from("-- a/b.txt --\nfoo\n").materialize_into(&dir);
+        let escape = Archive::from("-- link/c.txt --\nbar\n").materialize_into(&dir);
+        let escaped = root.join("outside/c.txt").exists();
+        let written = fs::read_to_string(root.join("sandbox/a/b.txt"));
+
+        assert_eq!(ok.unwrap().dirs_created, 1);
+        assert_eq!(written.unwrap(), "foo\n");
+        assert!(escape.is_err());
+        assert!(!escaped);
+    }
+}
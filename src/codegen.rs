@@ -0,0 +1,190 @@
+//! Embedding directories of `txtar` files into a crate from a build script.
+//!
+//! [generate] finds each `.txtar` file under a directory and writes a Rust module exposing each of
+//! them as a [StaticArchive][crate::StaticArchive] constant, so that test binaries can access
+//! their fixtures without touching the file system or parsing anything at runtime.
+//!
+//! ## Example
+//! In `build.rs`, with `simple_txtar` listed under `[build-dependencies]`:
+//! ```no_run
+//! use std::{env, path::Path};
+//!
+//! let out = Path::new(&env::var("OUT_DIR").unwrap()).join("fixtures.rs");
+//! simple_txtar::codegen::generate("tests/data", out).unwrap();
+//! ```
+//!
+//! Then wherever the fixtures are needed:
+//! ```ignore
+//! mod fixtures {
+//!     include!(concat!(env!("OUT_DIR"), "/fixtures.rs"));
+//! }
+//!
+//! // Generated from tests/data/nested/basic-case.txtar
+//! let a = fixtures::NESTED_BASIC_CASE;
+//! ```
+use crate::{scan::validate, Archive};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Generate a Rust module at `out` containing a [StaticArchive][crate::StaticArchive] constant
+/// for each `.txtar` file found under `dir`.
+///
+/// Directories are searched recursively and the name of each constant is the path of the file
+/// relative to `dir` with its extension removed, converted to upper case and with any characters
+/// that are not ASCII letters or digits replaced by `_`. This will error if two files map to the
+/// same constant name or if any of the files can not be read.
+///
+/// Each file is also validated, with an error of kind [io::ErrorKind::InvalidData] listing the
+/// path and line number of every problem found if it contains duplicate, empty or absolute file
+/// names, file names with `..` components or is not in canonical form (see
+/// [is_canonical][crate::is_canonical]). This catches mistakes in fixtures when they are built
+/// rather than when the tests using them are run. The generated module is only
+/// rewritten if its content has changed, and `cargo:rerun-if-changed` directives are printed for
+/// `dir` and each file found so that the build script is re-run when the fixtures change.
+pub fn generate(dir: impl AsRef<Path>, out: impl AsRef<Path>) -> io::Result<()> {
+    let dir = dir.as_ref();
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut paths = Vec::new();
+    find_txtar_files(dir, &mut paths)?;
+    paths.sort();
+
+    let mut names: HashMap<String, PathBuf> = HashMap::new();
+    let mut s = String::from("// Generated by simple_txtar::codegen. Do not edit.\n");
+    for path in paths {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let relative = path.strip_prefix(dir).unwrap_or(&path);
+        let name = const_name(&relative.with_extension(""));
+        if let Some(existing) = names.insert(name.clone(), path.clone()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{existing:?} and {path:?} would both be named {name}"),
+            ));
+        }
+
+        let a = validated(&path)?;
+        s.push_str(&format!(
+            "\n/// Generated from `{}`\npub const {name}: ::simple_txtar::StaticArchive = {};\n",
+            relative.display(),
+            a.to_rust_source()
+        ));
+    }
+
+    let out = out.as_ref();
+    if fs::read_to_string(out).is_ok_and(|existing| existing == s) {
+        return Ok(());
+    }
+
+    fs::write(out, s)
+}
+
+/// Read the archive at `path`, returning an error describing every problem found with it.
+fn validated(path: &Path) -> io::Result<Archive> {
+    let raw = crate::read_to_string(path)?;
+    let problems = validate(&raw);
+    if problems.is_empty() {
+        return Ok(Archive::from(raw));
+    }
+
+    let msg = problems
+        .iter()
+        .map(|(line, problem)| format!("{}:{line}: {problem}", path.display()))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid txtar file\n{msg}"),
+    ))
+}
+
+fn find_txtar_files(dir: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_txtar_files(&path, paths)?;
+        } else if path.extension().is_some_and(|ext| ext == "txtar") {
+            paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn const_name(path: &Path) -> String {
+    let name: String = path
+        .to_string_lossy()
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect();
+
+    match name.starts_with(|c: char| c.is_ascii_digit()) || name.is_empty() {
+        true => format!("_{name}"),
+        false => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn constants_are_named_after_paths() {
+        let dir = TestDir::new("codegen");
+        Archive::from("-- b.txtar --\n-- ignored.txt --\n-- nested/1-a.txtar --\n")
+            .materialize(&dir)
+            .unwrap();
+        fs::write(dir.join("nested/1-a.txtar"), "-- a.txt --\nfoo\n").unwrap();
+        let out = dir.join("out.rs");
+
+        let res = generate(&dir, &out);
+        let generated = fs::read_to_string(&out);
+        fs::write(dir.join("nested_1_a.txtar"), "").unwrap();
+        let collision = generate(&dir, &out);
+
+        assert!(res.is_ok());
+        let generated = generated.unwrap();
+        assert!(generated.contains("pub const B: ::simple_txtar::StaticArchive ="));
+        assert!(generated.contains("pub const NESTED_1_A: ::simple_txtar::StaticArchive ="));
+        assert!(generated.contains("StaticFile::new(\"a.txt\", r#\"foo\n\"#)"));
+        assert!(!generated.contains("IGNORED"));
+        assert!(collision.is_err());
+    }
+
+    #[test]
+    fn invalid_fixtures_are_rejected() {
+        let dir = TestDir::new("invalid");
+        let path = dir.join("invalid.txtar");
+        fs::write(
+            &path,
+            "comment\n-- a --\n-- ../b --\n-- a --\n--  c --\nfoo",
+        )
+        .unwrap();
+        let err = validated(&path).unwrap_err();
+        fs::write(&path, "comment\n-- a --\nfoo\n").unwrap();
+        let ok = validated(&path);
+
+        let msg = err.to_string();
+        let lines: Vec<&str> = msg.lines().skip(1).collect();
+        let p = path.display();
+        assert_eq!(
+            lines,
+            vec![
+                format!("{p}:3: file name contains a '..' component"),
+                format!("{p}:4: duplicate file name"),
+                format!(
+                    "{p}:5: not in canonical form (check for a byte order mark, extra whitespace \
+                     around file names, CRLF file markers or a missing trailing newline)"
+                ),
+            ]
+        );
+        assert!(ok.is_ok());
+    }
+}
@@ -0,0 +1,186 @@
+//! An archive storing short strings inline using [compact_str].
+use crate::{fmt_with_trailing_newline, Archive, ArchiveRef, File, MARKER, MARKER_END};
+use compact_str::CompactString;
+use std::{fmt, ops::Index, slice::Iter};
+
+/// A `txtar` archive that stores its file names and contents as [CompactString]s.
+///
+/// Strings of up to 24 bytes are stored inline without a heap allocation, which covers most file
+/// names and many small fixture files. For test suites that load large numbers of archives this
+/// can significantly reduce the number of allocations compared to an [Archive].
+///
+/// Parsing goes through [ArchiveRef] so that short names and contents are never allocated at
+/// all.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::{Archive, CompactArchive};
+///
+/// let s = "-- a.txt --\nfoo\n-- b.txt --\nbar\n";
+/// let a = CompactArchive::from(s);
+///
+/// assert!(!a["a.txt"].content.is_heap_allocated());
+/// assert_eq!(a.to_archive(), Archive::from(s));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CompactArchive {
+    comment: CompactString,
+    files: Vec<CompactFile>,
+}
+
+impl CompactArchive {
+    /// The optional comment at the top of the `txtar` archive.
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// Attempt to get a [CompactFile] from the archive by name, returning the first match.
+    pub fn get(&self, filename: &str) -> Option<&CompactFile> {
+        self.files.iter().find(|f| f.name == filename)
+    }
+
+    /// Iterate over the [CompactFile]s contained in this archive in order.
+    pub fn iter(&self) -> Iter<'_, CompactFile> {
+        self.files.iter()
+    }
+
+    /// Add a new [CompactFile] to the end of the archive.
+    pub fn add_file(&mut self, file: impl Into<CompactFile>) {
+        self.files.push(file.into());
+    }
+
+    /// The number of files in the archive.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether or not the archive contains any files.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Copy the contents of this archive into a new [Archive].
+    pub fn to_archive(&self) -> Archive {
+        Archive {
+            comment: self.comment.to_string(),
+            files: self
+                .files
+                .iter()
+                .map(|f| File::new(f.name.as_str(), f.content.as_str()))
+                .collect(),
+        }
+    }
+}
+
+impl From<&str> for CompactArchive {
+    fn from(s: &str) -> Self {
+        let a = ArchiveRef::from(s);
+
+        Self {
+            comment: CompactString::from(a.comment()),
+            files: a
+                .iter()
+                .map(|f| CompactFile::new(f.name, f.content.as_ref()))
+                .collect(),
+        }
+    }
+}
+
+impl From<&Archive> for CompactArchive {
+    fn from(a: &Archive) -> Self {
+        Self {
+            comment: CompactString::from(a.comment()),
+            files: a
+                .iter()
+                .map(|f| CompactFile::new(f.name.as_str(), f.content.as_str()))
+                .collect(),
+        }
+    }
+}
+
+impl From<CompactArchive> for Archive {
+    fn from(a: CompactArchive) -> Self {
+        a.to_archive()
+    }
+}
+
+impl Index<usize> for CompactArchive {
+    type Output = CompactFile;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.files[index]
+    }
+}
+
+impl Index<&str> for CompactArchive {
+    type Output = CompactFile;
+
+    fn index(&self, filename: &str) -> &Self::Output {
+        self.get(filename).unwrap()
+    }
+}
+
+impl<'a> IntoIterator for &'a CompactArchive {
+    type Item = &'a CompactFile;
+    type IntoIter = Iter<'a, CompactFile>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.iter()
+    }
+}
+
+impl fmt::Display for CompactArchive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_with_trailing_newline(f, &self.comment)?;
+        for file in self.files.iter() {
+            writeln!(f, "{MARKER}{}{MARKER_END}", file.name)?;
+            fmt_with_trailing_newline(f, &file.content)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A single file within a [CompactArchive].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct CompactFile {
+    /// The name of the file
+    pub name: CompactString,
+    /// The content of the file
+    pub content: CompactString,
+}
+
+impl CompactFile {
+    /// Create a new [CompactFile] with the given name and content.
+    pub fn new(name: impl Into<CompactString>, content: impl Into<CompactString>) -> Self {
+        Self {
+            name: name.into(),
+            content: content.into(),
+        }
+    }
+}
+
+impl<T, U> From<(T, U)> for CompactFile
+where
+    T: Into<CompactString>,
+    U: Into<CompactString>,
+{
+    fn from((name, content): (T, U)) -> Self {
+        Self::new(name, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_archive() {
+        let s = "\u{feff}comment\n-- a --\nfoo\n--  b  --\n";
+        let a = CompactArchive::from(s);
+
+        assert_eq!(a, CompactArchive::from(&Archive::from(s)));
+        assert_eq!(a.to_string(), Archive::from(s).to_string());
+        assert!(a.iter().all(|f| !f.name.is_heap_allocated()));
+    }
+}
@@ -0,0 +1,251 @@
+//! Integration with [miette] for reporting errors against the source text of an archive.
+use crate::{Lint, LintKind, ParseError, ParseErrorKind};
+use miette::{Diagnostic, LabeledSpan, NamedSource, Severity, SourceCode, SourceSpan};
+use std::{error, fmt};
+
+/// A [ParseError] or [Lint] paired with the source text that it was found in, allowing it to be
+/// rendered by [miette] with a label pointing at the offending line.
+///
+/// Created using [ParseError::with_source_code] or [Lint::with_source_code].
+#[derive(Debug)]
+pub struct SourceDiagnostic<E> {
+    inner: E,
+    src: NamedSource<String>,
+    span: Option<SourceSpan>,
+}
+
+impl<E> SourceDiagnostic<E> {
+    /// The underlying error or lint.
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// Consume the diagnostic, returning the underlying error or lint.
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for SourceDiagnostic<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<E: error::Error> error::Error for SourceDiagnostic<E> {}
+
+impl<E: Diagnostic + Spanned> Diagnostic for SourceDiagnostic<E> {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.inner.code()
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        self.inner.severity()
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.inner.help()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.src)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let span = self.span?;
+        let label = LabeledSpan::new_with_span(Some(self.inner.label()), span);
+
+        Some(Box::new(std::iter::once(label)))
+    }
+}
+
+/// Errors that can be located at a line within the source text of an archive.
+pub trait Spanned {
+    /// The 1-based line number of the problem, if known.
+    fn line(&self) -> Option<usize>;
+    /// A short description of the problem to attach to the labeled span.
+    fn label(&self) -> String;
+}
+
+impl Spanned for ParseError {
+    fn line(&self) -> Option<usize> {
+        Some(self.line)
+    }
+
+    fn label(&self) -> String {
+        self.kind.to_string()
+    }
+}
+
+impl Spanned for Lint {
+    fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    fn label(&self) -> String {
+        self.kind.to_string()
+    }
+}
+
+impl ParseError {
+    /// Attach the source text that this error was found in so that it can be reported by
+    /// [miette] with a label pointing at the offending line.
+    ///
+    /// `name` is used as the name of the source in the rendered report and `src` should be the
+    /// same text that was passed to the parser.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, ParseOptions};
+    ///
+    /// let s = "-- a --\nfoo\n-- b--\nbar\n";
+    /// let err = Archive::from_str_with(s, ParseOptions::new().strict(true)).unwrap_err();
+    /// let report = miette::Report::new(err.with_source_code("fixture.txtar", s));
+    ///
+    /// assert_eq!(report.to_string(), "line 3: malformed file marker \"-- b--\"");
+    /// ```
+    pub fn with_source_code(
+        self,
+        name: impl AsRef<str>,
+        src: impl Into<String>,
+    ) -> SourceDiagnostic<Self> {
+        SourceDiagnostic::new(self, name, src.into())
+    }
+}
+
+impl Lint {
+    /// Attach the source text of the archive that this lint was found in so that it can be
+    /// reported by [miette] with a label pointing at the offending line.
+    ///
+    /// `name` is used as the name of the source in the rendered report and `src` should be the
+    /// text that the linted archive was parsed from.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let s = "-- a --\nfoo\n-- ../b --\nbar\n";
+    /// let lint = Archive::from(s).lint().remove(0);
+    /// let report = miette::Report::new(lint.with_source_code("fixture.txtar", s));
+    ///
+    /// assert_eq!(report.severity(), Some(miette::Severity::Warning));
+    /// ```
+    pub fn with_source_code(
+        self,
+        name: impl AsRef<str>,
+        src: impl Into<String>,
+    ) -> SourceDiagnostic<Self> {
+        SourceDiagnostic::new(self, name, src.into())
+    }
+}
+
+impl<E: Spanned> SourceDiagnostic<E> {
+    fn new(inner: E, name: impl AsRef<str>, src: String) -> Self {
+        let span = inner.line().map(|line| line_span(&src, line));
+
+        Self {
+            inner,
+            src: NamedSource::new(name, src),
+            span,
+        }
+    }
+}
+
+impl Diagnostic for ParseError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let code = match self.kind {
+            ParseErrorKind::DuplicateName(_) => "simple_txtar::duplicate_name",
+            ParseErrorKind::EmptyName => "simple_txtar::empty_name",
+            ParseErrorKind::MalformedMarker(_) => "simple_txtar::malformed_marker",
+            ParseErrorKind::TooManyFiles { .. } => "simple_txtar::too_many_files",
+            ParseErrorKind::InputTooLarge { .. } => "simple_txtar::input_too_large",
+            ParseErrorKind::FileTooLarge { .. } => "simple_txtar::file_too_large",
+        };
+
+        Some(Box::new(code))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let help = match self.kind {
+            ParseErrorKind::DuplicateName(_) => "file names must be unique within an archive",
+            ParseErrorKind::EmptyName => "file markers must contain a file name",
+            ParseErrorKind::MalformedMarker(_) => "file markers have the form `-- name --`",
+            _ => return None,
+        };
+
+        Some(Box::new(help))
+    }
+}
+
+impl Diagnostic for Lint {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let code = match self.kind {
+            LintKind::DuplicateName => "simple_txtar::lint::duplicate_name",
+            LintKind::EmptyName => "simple_txtar::lint::empty_name",
+            LintKind::AbsolutePath => "simple_txtar::lint::absolute_path",
+            LintKind::ParentDir => "simple_txtar::lint::parent_dir",
+            LintKind::NearMissMarker(_) => "simple_txtar::lint::near_miss_marker",
+            LintKind::MixedLineEndings => "simple_txtar::lint::mixed_line_endings",
+        };
+
+        Some(Box::new(code))
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Warning)
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let help = match self.kind {
+            LintKind::NearMissMarker(_) => "file markers have the form `-- name --`",
+            LintKind::AbsolutePath | LintKind::ParentDir => {
+                "file names should be relative paths within the archive"
+            }
+            _ => return None,
+        };
+
+        Some(Box::new(help))
+    }
+}
+
+/// The byte range of the given 1-based line within `src`, excluding its line ending.
+fn line_span(src: &str, line: usize) -> SourceSpan {
+    let mut offset = 0;
+    for (i, l) in src.split_inclusive('\n').enumerate() {
+        if i + 1 == line {
+            let len = l.trim_end_matches(['\r', '\n']).len();
+            return (offset, len).into();
+        }
+        offset += l.len();
+    }
+
+    (src.len(), 0).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Archive, ParseOptions};
+
+    #[test]
+    fn line_span_excludes_line_endings() {
+        let src = "-- a --\nfoo\r\n-- b--\r\nbar";
+
+        assert_eq!(line_span(src, 1), (0, 7).into());
+        assert_eq!(line_span(src, 3), (13, 6).into());
+        assert_eq!(line_span(src, 9), (src.len(), 0).into());
+    }
+
+    #[test]
+    fn strict_errors_are_labeled() {
+        let s = "-- a --\nfoo\n--  --\n";
+        let err = Archive::from_str_with(s, ParseOptions::new().strict(true)).unwrap_err();
+        let diag = err.with_source_code("test", s);
+        let labels: Vec<_> = diag.labels().unwrap().collect();
+
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].offset(), 12);
+        assert_eq!(labels[0].len(), 6);
+        assert_eq!(labels[0].label(), Some("empty file name"));
+    }
+}
@@ -0,0 +1,327 @@
+//! Line based unified diffs between two texts.
+use std::fmt::Write;
+
+/// The number of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// A unified diff from `a` to `b` with `CONTEXT` lines of context, or an empty string if they
+/// are equal.
+pub(crate) fn unified_diff(a_name: &str, b_name: &str, a: &str, b: &str) -> String {
+    let a_lines: Vec<&str> = a.split_inclusive('\n').collect();
+    let b_lines: Vec<&str> = b.split_inclusive('\n').collect();
+    let ops = edit_script(&a_lines, &b_lines);
+    if ops.iter().all(|(op, _)| *op == Op::Equal) {
+        return String::new();
+    }
+
+    let mut s = format!("--- {a_name}\n+++ {b_name}\n");
+    let changes: Vec<usize> = (0..ops.len()).filter(|&i| ops[i].0 != Op::Equal).collect();
+    let mut i = 0;
+    while i < changes.len() {
+        // Merge changes whose context would overlap into a single hunk
+        let mut j = i;
+        while j + 1 < changes.len() && changes[j + 1] - changes[j] <= 2 * CONTEXT {
+            j += 1;
+        }
+        let start = changes[i].saturating_sub(CONTEXT);
+        let end = (changes[j] + CONTEXT + 1).min(ops.len());
+        write_hunk(&mut s, &ops, start, end);
+        i = j + 1;
+    }
+
+    s
+}
+
+fn write_hunk(s: &mut String, ops: &[(Op, &str)], start: usize, end: usize) {
+    let count = |ops: &[(Op, &str)], skip: Op| ops.iter().filter(|(op, _)| *op != skip).count();
+    let (a_before, b_before) = (
+        count(&ops[..start], Op::Insert),
+        count(&ops[..start], Op::Delete),
+    );
+    let (a_len, b_len) = (
+        count(&ops[start..end], Op::Insert),
+        count(&ops[start..end], Op::Delete),
+    );
+
+    let _ = writeln!(
+        s,
+        "@@ -{} +{} @@",
+        range(a_before, a_len),
+        range(b_before, b_len)
+    );
+    for (op, line) in &ops[start..end] {
+        s.push(match op {
+            Op::Equal => ' ',
+            Op::Delete => '-',
+            Op::Insert => '+',
+        });
+        s.push_str(line);
+        if !line.ends_with('\n') {
+            s.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+}
+
+fn range(before: usize, len: usize) -> String {
+    match len {
+        0 => format!("{before},0"),
+        1 => format!("{}", before + 1),
+        _ => format!("{},{len}", before + 1),
+    }
+}
+
+/// The operations needed to turn `a` into `b`, found using Myers' O(ND) difference algorithm in
+/// linear space so that comparing large inputs with few changes between them is cheap.
+fn edit_script<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<(Op, &'a str)> {
+    let mut ops = Vec::with_capacity(a.len().max(b.len()));
+    diff(a, b, &mut ops);
+
+    // List the deleted lines in each run of changes before the inserted ones, as `diff -u` does
+    for run in ops.chunk_by_mut(|x, y| (x.0 == Op::Equal) == (y.0 == Op::Equal)) {
+        run.sort_by_key(|(op, _)| *op == Op::Insert);
+    }
+
+    ops
+}
+
+/// Append the operations needed to turn `a` into `b` to `ops`.
+fn diff<'a>(a: &[&'a str], b: &[&'a str], ops: &mut Vec<(Op, &'a str)>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    ops.extend(a[..prefix].iter().map(|l| (Op::Equal, *l)));
+    match bisect(a_mid, b_mid) {
+        Some((x, y)) => {
+            diff(&a_mid[..x], &b_mid[..y], ops);
+            diff(&a_mid[x..], &b_mid[y..], ops);
+        }
+        None => {
+            ops.extend(a_mid.iter().map(|l| (Op::Delete, *l)));
+            ops.extend(b_mid.iter().map(|l| (Op::Insert, *l)));
+        }
+    }
+    ops.extend(a[a.len() - suffix..].iter().map(|l| (Op::Equal, *l)));
+}
+
+/// Find a point that splits a shortest edit script from `a` to `b` in two, by following the
+/// furthest reaching paths from both ends of the edit graph until they meet in the middle. Only
+/// the furthest point reached on each diagonal is stored, so this needs O(N) space.
+///
+/// Returns `None` if there is nothing in common between `a` and `b`.
+fn bisect(a: &[&str], b: &[&str]) -> Option<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    if n == 0 || m == 0 {
+        return None;
+    }
+
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d;
+    let mut forward = vec![-1; 2 * max_d as usize + 2];
+    let mut backward = forward.clone();
+    forward[offset as usize + 1] = 0;
+    backward[offset as usize + 1] = 0;
+
+    let delta = n - m;
+    // Paths can only overlap on the forward pass when delta is odd and the backward pass when even
+    let front = delta % 2 != 0;
+    // Diagonals that have left the edit graph are skipped on later passes
+    let (mut k1_start, mut k1_end, mut k2_start, mut k2_end) = (0, 0, 0, 0);
+
+    for d in 0..max_d {
+        for k1 in (-d + k1_start..=d - k1_end).step_by(2) {
+            let k1_offset = (offset + k1) as usize;
+            let mut x1 =
+                match k1 == -d || (k1 != d && forward[k1_offset - 1] < forward[k1_offset + 1]) {
+                    true => forward[k1_offset + 1],
+                    false => forward[k1_offset - 1] + 1,
+                };
+            let mut y1 = x1 - k1;
+            while x1 < n && y1 < m && a[x1 as usize] == b[y1 as usize] {
+                (x1, y1) = (x1 + 1, y1 + 1);
+            }
+            forward[k1_offset] = x1;
+
+            if x1 > n {
+                k1_end += 2;
+            } else if y1 > m {
+                k1_start += 2;
+            } else if front {
+                let k2_offset = offset + delta - k1;
+                if (0..backward.len() as isize).contains(&k2_offset)
+                    && backward[k2_offset as usize] != -1
+                    && x1 >= n - backward[k2_offset as usize]
+                {
+                    return Some((x1 as usize, y1 as usize));
+                }
+            }
+        }
+
+        for k2 in (-d + k2_start..=d - k2_end).step_by(2) {
+            let k2_offset = (offset + k2) as usize;
+            let mut x2 =
+                match k2 == -d || (k2 != d && backward[k2_offset - 1] < backward[k2_offset + 1]) {
+                    true => backward[k2_offset + 1],
+                    false => backward[k2_offset - 1] + 1,
+                };
+            let mut y2 = x2 - k2;
+            while x2 < n && y2 < m && a[(n - x2 - 1) as usize] == b[(m - y2 - 1) as usize] {
+                (x2, y2) = (x2 + 1, y2 + 1);
+            }
+            backward[k2_offset] = x2;
+
+            if x2 > n {
+                k2_end += 2;
+            } else if y2 > m {
+                k2_start += 2;
+            } else if !front {
+                let k1_offset = offset + delta - k2;
+                if (0..forward.len() as isize).contains(&k1_offset)
+                    && forward[k1_offset as usize] != -1
+                {
+                    let x1 = forward[k1_offset as usize];
+                    let y1 = offset + x1 - k1_offset;
+                    if x1 >= n - x2 {
+                        return Some((x1 as usize, y1 as usize));
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unified_diff_matches_diff_u() {
+        let a = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15";
+        let b = "1\n2\n3\nfour\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n16\n";
+
+        assert_eq!(unified_diff("a", "b", a, a), "");
+        assert_eq!(
+            unified_diff("a", "b", a, b),
+            "\
+--- a
++++ b
+@@ -1,7 +1,7 @@
+ 1
+ 2
+ 3
+-4
++four
+ 5
+ 6
+ 7
+@@ -12,4 +12,5 @@
+ 12
+ 13
+ 14
+-15
+\\ No newline at end of file
++15
++16
+"
+        );
+        assert_eq!(
+            unified_diff("a", "b", "", "x\n"),
+            "--- a\n+++ b\n@@ -0,0 +1 @@\n+x\n"
+        );
+    }
+
+    #[test]
+    fn edit_scripts_are_minimal() {
+        // The number of unchanged lines in a shortest edit script is the length of the longest
+        // common subsequence, which is cheap to find directly for small inputs.
+        fn lcs(a: &[&str], b: &[&str]) -> usize {
+            let mut row = vec![0; b.len() + 1];
+            for x in a {
+                let mut prev = 0;
+                for (j, y) in b.iter().enumerate() {
+                    let next = if x == y {
+                        prev + 1
+                    } else {
+                        row[j + 1].max(row[j])
+                    };
+                    prev = row[j + 1];
+                    row[j + 1] = next;
+                }
+            }
+            row[b.len()]
+        }
+
+        let mut seed = 1u64;
+        let mut lines = |len: usize| -> Vec<&str> {
+            (0..len)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                    ["a\n", "b\n", "c\n"][(seed >> 33) as usize % 3]
+                })
+                .collect()
+        };
+
+        for i in 0..500 {
+            let (a, b) = (lines(i % 13), lines(i % 7 + i % 5));
+            let ops = edit_script(&a, &b);
+            let kept = |skip| {
+                ops.iter()
+                    .filter(move |(op, _)| *op != skip)
+                    .map(|(_, l)| *l)
+            };
+
+            assert_eq!(kept(Op::Insert).collect::<Vec<_>>(), a);
+            assert_eq!(kept(Op::Delete).collect::<Vec<_>>(), b);
+            assert_eq!(
+                ops.iter().filter(|(op, _)| *op == Op::Equal).count(),
+                lcs(&a, &b),
+                "{a:?} {b:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn large_inputs_with_few_changes_are_cheap() {
+        let a: String = (0..20_000).map(|i| format!("{i}\n")).collect();
+        let b = a.replacen("10\n", "ten\n", 1).replacen("19990\n", "", 1);
+
+        assert_eq!(
+            unified_diff("a", "b", &a, &b),
+            "\
+--- a
++++ b
+@@ -8,7 +8,7 @@
+ 7
+ 8
+ 9
+-10
++ten
+ 11
+ 12
+ 13
+@@ -19988,7 +19988,6 @@
+ 19987
+ 19988
+ 19989
+-19990
+ 19991
+ 19992
+ 19993
+"
+        );
+    }
+}
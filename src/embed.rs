@@ -0,0 +1,405 @@
+//! Archives that are embedded in a binary as compiled-in data.
+use crate::{fix_trailing_newline, fmt_with_trailing_newline, scan::strip_bom, Archive, File};
+use std::{collections::HashSet, fmt, ops::Index, path::Path, slice::Iter};
+
+/// A `txtar` archive whose comment and files are `'static` data, typically generated ahead of
+/// time using [Archive::to_rust_source].
+///
+/// Unlike [Archive] and [ArchiveRef][crate::ArchiveRef], a [StaticArchive] can be constructed in
+/// a `const` context so it can be stored in a `static` without needing to be parsed at runtime.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::{Archive, StaticArchive, StaticFile};
+///
+/// static FIXTURE: StaticArchive = StaticArchive::new(
+///     "comment\n",
+///     &[StaticFile::new("a.txt", "foo\n"), StaticFile::new("b.txt", "bar\n")],
+/// );
+///
+/// assert_eq!(FIXTURE["a.txt"].content, "foo\n");
+/// assert_eq!(
+///     FIXTURE.to_archive(),
+///     Archive::from("comment\n-- a.txt --\nfoo\n-- b.txt --\nbar\n")
+/// );
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StaticArchive {
+    comment: &'static str,
+    files: &'static [StaticFile],
+}
+
+impl StaticArchive {
+    /// Construct a new [StaticArchive] from its comment and files.
+    pub const fn new(comment: &'static str, files: &'static [StaticFile]) -> Self {
+        Self { comment, files }
+    }
+
+    /// The optional comment at the top of the `txtar` archive.
+    pub const fn comment(&self) -> &'static str {
+        self.comment
+    }
+
+    /// The files contained in this archive in the order they were specified in the original
+    /// `txtar` file.
+    pub const fn files(&self) -> &'static [StaticFile] {
+        self.files
+    }
+
+    /// Attempt to get a [StaticFile] from the archive by name, returning the first match.
+    pub fn get(&self, filename: &str) -> Option<&'static StaticFile> {
+        self.files.iter().find(|f| f.name == filename)
+    }
+
+    /// Iterate over the [StaticFile]s contained in this archive in the order they were specified
+    /// in the original `txtar` file.
+    pub fn iter(&self) -> Iter<'static, StaticFile> {
+        self.files.iter()
+    }
+
+    /// The number of files in the archive.
+    pub const fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether or not the archive contains any files.
+    pub const fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// The top level directory of the archive, for accessing its files as a directory tree in
+    /// the same way as an embedded directory.
+    ///
+    /// Directories are implied by the names of the files, using `/` as the path separator, so
+    /// empty directories can not be represented.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{StaticArchive, StaticFile};
+    ///
+    /// static FIXTURE: StaticArchive = StaticArchive::new(
+    ///     "",
+    ///     &[
+    ///         StaticFile::new("README", "hi\n"),
+    ///         StaticFile::new("src/lib.rs", "\n"),
+    ///         StaticFile::new("src/bin/main.rs", "\n"),
+    ///     ],
+    /// );
+    ///
+    /// let root = FIXTURE.root();
+    /// let names = |files: Vec<&StaticFile>| files.iter().map(|f| f.name).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(names(root.files().collect()), ["README"]);
+    /// assert_eq!(root.dirs()[0].path().to_str(), Some("src"));
+    /// assert_eq!(names(root.walk().collect()), ["README", "src/lib.rs", "src/bin/main.rs"]);
+    /// assert_eq!(names(FIXTURE.get_dir("src/bin").unwrap().walk().collect()), ["src/bin/main.rs"]);
+    /// ```
+    pub const fn root(&self) -> StaticDir {
+        StaticDir {
+            path: "",
+            files: self.files,
+        }
+    }
+
+    /// Attempt to get a [StaticFile] from the archive by its path, returning the first match.
+    ///
+    /// Any leading `./` is ignored.
+    pub fn get_file(&self, path: &str) -> Option<&'static StaticFile> {
+        self.get(path.strip_prefix("./").unwrap_or(path))
+    }
+
+    /// Attempt to get the directory at the given path, returning `None` if no file in the
+    /// archive is beneath it.
+    ///
+    /// Any leading `./` or trailing `/` is ignored and an empty path refers to [StaticArchive::root].
+    pub fn get_dir(&self, path: &str) -> Option<StaticDir> {
+        let path = path
+            .strip_prefix("./")
+            .unwrap_or(path)
+            .trim_end_matches('/');
+        if path.is_empty() {
+            return Some(self.root());
+        }
+
+        self.files.iter().find_map(|f| {
+            let rest = f.name.strip_prefix(path)?.strip_prefix('/')?;
+            Some(StaticDir {
+                path: &f.name[..f.name.len() - rest.len() - 1],
+                files: self.files,
+            })
+        })
+    }
+
+    /// Copy the contents of this archive into an owned [Archive].
+    pub fn to_archive(&self) -> Archive {
+        Archive {
+            comment: self.comment.to_string(),
+            files: self.files.iter().map(File::from).collect(),
+        }
+    }
+}
+
+impl From<StaticArchive> for Archive {
+    fn from(a: StaticArchive) -> Self {
+        a.to_archive()
+    }
+}
+
+impl Index<usize> for StaticArchive {
+    type Output = StaticFile;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.files[index]
+    }
+}
+
+impl Index<&str> for StaticArchive {
+    type Output = StaticFile;
+
+    fn index(&self, filename: &str) -> &Self::Output {
+        self.get(filename).unwrap()
+    }
+}
+
+impl IntoIterator for StaticArchive {
+    type Item = &'static StaticFile;
+    type IntoIter = Iter<'static, StaticFile>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.iter()
+    }
+}
+
+impl fmt::Display for StaticArchive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_with_trailing_newline(f, strip_bom(self.comment))?;
+        for file in self.files.iter() {
+            write!(f, "{file}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A single file within a [StaticArchive].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StaticFile {
+    /// The name of the file
+    pub name: &'static str,
+    /// The content of the file
+    pub content: &'static str,
+}
+
+impl StaticFile {
+    /// Construct a new [StaticFile].
+    pub const fn new(name: &'static str, content: &'static str) -> Self {
+        Self { name, content }
+    }
+
+    /// The name of this file as a path relative to the root of the archive.
+    pub fn path(&self) -> &'static Path {
+        Path::new(self.name)
+    }
+}
+
+impl From<StaticFile> for File {
+    fn from(f: StaticFile) -> Self {
+        File::new(f.name, f.content)
+    }
+}
+
+impl From<&StaticFile> for File {
+    fn from(f: &StaticFile) -> Self {
+        File::from(*f)
+    }
+}
+
+impl fmt::Display for StaticFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "-- {} --", self.name)?;
+        fmt_with_trailing_newline(f, self.content)
+    }
+}
+
+/// A directory within a [StaticArchive], as returned by [StaticArchive::root] and
+/// [StaticArchive::get_dir].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StaticDir {
+    path: &'static str,
+    files: &'static [StaticFile],
+}
+
+impl StaticDir {
+    /// The path of this directory relative to the root of the archive, which is empty for the
+    /// root itself.
+    pub fn path(&self) -> &'static Path {
+        Path::new(self.path)
+    }
+
+    /// The files directly inside of this directory, in the order they appear in the archive.
+    pub fn files(&self) -> impl Iterator<Item = &'static StaticFile> + '_ {
+        self.children()
+            .filter(|(_, rest)| !rest.contains('/'))
+            .map(|(f, _)| f)
+    }
+
+    /// The directories directly inside of this directory, in the order they first appear in the
+    /// archive.
+    pub fn dirs(&self) -> Vec<StaticDir> {
+        let mut seen = HashSet::new();
+
+        self.children()
+            .filter_map(|(f, rest)| {
+                let (name, _) = rest.split_once('/')?;
+                let len = f.name.len() - rest.len() + name.len();
+                seen.insert(name).then_some(StaticDir {
+                    path: &f.name[..len],
+                    files: self.files,
+                })
+            })
+            .collect()
+    }
+
+    /// Recursively iterate over every file beneath this directory, in the order they appear in
+    /// the archive.
+    pub fn walk(&self) -> impl Iterator<Item = &'static StaticFile> + '_ {
+        self.children().map(|(f, _)| f)
+    }
+
+    /// Attempt to get a file beneath this directory by its path relative to this directory.
+    pub fn get_file(&self, path: &str) -> Option<&'static StaticFile> {
+        self.children()
+            .find(|(_, rest)| *rest == path)
+            .map(|(f, _)| f)
+    }
+
+    /// Each file beneath this directory along with its path relative to this directory.
+    fn children(&self) -> impl Iterator<Item = (&'static StaticFile, &'static str)> + '_ {
+        self.files.iter().filter_map(|f| {
+            let rest = match self.path {
+                "" => f.name,
+                dir => f.name.strip_prefix(dir)?.strip_prefix('/')?,
+            };
+            Some((f, rest))
+        })
+    }
+}
+
+impl Archive {
+    /// Render this archive as a Rust expression that constructs an equivalent [StaticArchive].
+    ///
+    /// This allows fixtures to be vendored as compiled-in data that does not need to be parsed
+    /// at runtime, by writing the output to a file and including it with [include]. Paths in the
+    /// generated code are fully qualified so it can be included from any module, and content is
+    /// written using raw string literals where possible so that it remains readable. Missing
+    /// trailing newlines are added in the same way as when the archive is written out as a
+    /// `txtar` file.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("-- a.txt --\n\"foo\"\n");
+    ///
+    /// assert_eq!(
+    ///     a.to_rust_source(),
+    ///     r##"::simple_txtar::StaticArchive::new(
+    ///     "",
+    ///     &[
+    ///         ::simple_txtar::StaticFile::new("a.txt", r#""foo"
+    /// "#),
+    ///     ],
+    /// )"##
+    /// );
+    /// ```
+    pub fn to_rust_source(&self) -> String {
+        let mut s = format!(
+            "::simple_txtar::StaticArchive::new(\n    {},\n    &[\n",
+            string_literal(&fix_trailing_newline(self.comment_without_bom()))
+        );
+        for f in self.files.iter() {
+            s.push_str(&format!(
+                "        ::simple_txtar::StaticFile::new({:?}, {}),\n",
+                f.name,
+                string_literal(&fix_trailing_newline(&f.content))
+            ));
+        }
+        s.push_str("    ],\n)");
+
+        s
+    }
+}
+
+/// A Rust string literal for `s`, using a raw string literal if it contains a newline and does not
+/// contain a carriage return, which is not permitted in raw strings.
+fn string_literal(s: &str) -> String {
+    if !s.contains('\n') || s.contains('\r') {
+        return format!("{s:?}");
+    }
+
+    let longest = s
+        .split('"')
+        .skip(1)
+        .map(|rest| rest.len() - rest.trim_start_matches('#').len())
+        .max()
+        .unwrap_or(0);
+    let hashes = "#".repeat(longest + 1);
+
+    format!("r{hashes}\"{s}\"{hashes}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directories_are_implied_by_file_names() {
+        static A: StaticArchive = StaticArchive::new(
+            "",
+            &[
+                StaticFile::new("a/b/c.txt", ""),
+                StaticFile::new("a/d.txt", ""),
+                StaticFile::new("ab/e.txt", ""),
+                StaticFile::new("a/b/f.txt", ""),
+            ],
+        );
+        let a = A.get_dir("./a/").unwrap();
+        let dirs: Vec<_> = a
+            .dirs()
+            .iter()
+            .map(|d| d.path().to_str().unwrap())
+            .collect();
+        let files: Vec<_> = a.files().map(|f| f.name).collect();
+        let walked: Vec<_> = a.walk().map(|f| f.name).collect();
+
+        assert_eq!(dirs, ["a/b"]);
+        assert_eq!(files, ["a/d.txt"]);
+        assert_eq!(walked, ["a/b/c.txt", "a/d.txt", "a/b/f.txt"]);
+        assert_eq!(a.get_file("b/f.txt"), A.get_file("a/b/f.txt"));
+        assert!(A.get_dir("a/b/c.txt").is_none());
+        assert!(A.get_dir("missing").is_none());
+    }
+
+    #[test]
+    fn display_matches_archive() {
+        static A: StaticArchive = StaticArchive::new(
+            "\u{feff}comment",
+            &[StaticFile::new("a", "foo"), StaticFile::new("b", "")],
+        );
+
+        assert_eq!(A.to_string(), A.to_archive().to_string());
+        assert_eq!(A.to_string(), "comment\n-- a --\nfoo\n-- b --\n");
+        assert_eq!(
+            A.files()[0].to_string(),
+            File::from(A.files()[0]).to_string()
+        );
+    }
+
+    #[test]
+    fn string_literals_are_escaped() {
+        assert_eq!(string_literal("a\"b\\"), r#""a\"b\\""#);
+        assert_eq!(string_literal("a\r\n"), r#""a\r\n""#);
+        assert_eq!(string_literal("\"##\n"), "r###\"\"##\n\"###");
+        assert_eq!(string_literal("plain\n"), "r#\"plain\n\"#");
+    }
+}
@@ -0,0 +1,175 @@
+//! Error types returned by fallible operations on an [Archive][crate::Archive].
+use crate::File;
+use std::{error, fmt, io};
+
+/// The error type for operations that can fail in more than one way, such as reading and parsing
+/// an archive from disk or writing an archive out to a directory.
+///
+/// The more specific error types returned by individual operations can all be converted into an
+/// [Error] using `?`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An error reading from or writing to the file system
+    Io(io::Error),
+    /// The input could not be parsed with the requested [ParseOptions][crate::ParseOptions]
+    Parse(ParseError),
+    /// The input exceeded one of the configured [Limits][crate::Limits]
+    Limit(ParseError),
+    /// A file name that can not be used for the requested operation
+    InvalidName {
+        /// The invalid file name
+        name: String,
+        /// Why the name was rejected
+        reason: &'static str,
+    },
+    /// A file could not be added to an archive as its name was already in use
+    DuplicateName(DuplicateName),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {e}"),
+            Self::Parse(e) => write!(f, "parse error: {e}"),
+            Self::Limit(e) => write!(f, "limit exceeded: {e}"),
+            Self::InvalidName { name, reason } => write!(f, "invalid file name {name:?}: {reason}"),
+            Self::DuplicateName(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Parse(e) | Self::Limit(e) => Some(e),
+            Self::InvalidName { .. } => None,
+            Self::DuplicateName(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Errors for exceeding the configured [Limits][crate::Limits] are converted to [Error::Limit]
+/// and all other parse errors to [Error::Parse].
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        match e.kind {
+            ParseErrorKind::TooManyFiles { .. }
+            | ParseErrorKind::InputTooLarge { .. }
+            | ParseErrorKind::FileTooLarge { .. } => Self::Limit(e),
+            _ => Self::Parse(e),
+        }
+    }
+}
+
+impl From<DuplicateName> for Error {
+    fn from(e: DuplicateName) -> Self {
+        Self::DuplicateName(e)
+    }
+}
+
+/// An attempt was made to add a [File] to an [Archive][crate::Archive] that already contains a
+/// file with the same name.
+///
+/// The rejected file is returned as part of the error so that it is not lost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateName {
+    /// The file that was not added to the archive
+    pub file: File,
+}
+
+impl fmt::Display for DuplicateName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "archive already contains a file named {:?}",
+            self.file.name
+        )
+    }
+}
+
+impl error::Error for DuplicateName {}
+
+/// An error encountered when parsing a `txtar` archive using
+/// [Archive::from_str_with][crate::Archive::from_str_with] or one of its variants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The 1-based line number within the input where the error was found
+    pub line: usize,
+    /// The type of error that was found
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// The specific problem found when parsing a `txtar` archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// A file marker with a name that has already been used earlier in the archive
+    DuplicateName(String),
+    /// A file marker with a name that is empty or only whitespace
+    EmptyName,
+    /// A line that looks like it was intended to be a file marker but is not valid
+    MalformedMarker(String),
+    /// The input contains more than the maximum number of files
+    TooManyFiles {
+        /// The configured maximum number of files
+        max: usize,
+    },
+    /// The input is larger than the maximum number of bytes
+    InputTooLarge {
+        /// The configured maximum size of the input in bytes
+        max: usize,
+    },
+    /// The content of a file is larger than the maximum number of bytes
+    FileTooLarge {
+        /// The name of the file
+        name: String,
+        /// The configured maximum size of a single file in bytes
+        max: usize,
+    },
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateName(name) => write!(f, "duplicate file name {name:?}"),
+            Self::EmptyName => write!(f, "empty file name"),
+            Self::MalformedMarker(line) => write!(f, "malformed file marker {line:?}"),
+            Self::TooManyFiles { max } => write!(f, "archive contains more than {max} files"),
+            Self::InputTooLarge { max } => write!(f, "input is larger than {max} bytes"),
+            Self::FileTooLarge { name, max } => {
+                write!(f, "file {name:?} is larger than {max} bytes")
+            }
+        }
+    }
+}
+
+/// An error returned by [unquote][crate::unquote] when its input was not quoted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnquoteError {
+    /// The 1-based line number of the first line in the input that is missing its `>` prefix
+    pub line: usize,
+}
+
+impl fmt::Display for UnquoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: unquoted line in quoted content", self.line)
+    }
+}
+
+impl error::Error for UnquoteError {}
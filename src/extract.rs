@@ -0,0 +1,929 @@
+//! Writing the contents of an [Archive] out to disk.
+use crate::{
+    progress::{Progress, ProgressFn},
+    scan::marker_name,
+    Archive, Error, File,
+};
+use std::{
+    borrow::Cow,
+    ffi::OsString,
+    fmt, fs,
+    io::{self, BufRead, Write},
+    path::{Component, Path, PathBuf},
+    process, str,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// How files that already exist on disk are handled when extracting an archive with
+/// [Archive::materialize_with].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Conflict {
+    /// Replace the existing file with the content from the archive
+    #[default]
+    Overwrite,
+    /// Leave the existing file as it is and do not write the file from the archive
+    Skip,
+    /// Return an error without writing any files
+    Error,
+}
+
+/// Configuration for writing an [Archive] to disk using [Archive::materialize_with].
+///
+/// The default options match the behaviour of [Archive::materialize].
+///
+/// ## Example
+/// ```no_run
+/// use simple_txtar::{Archive, Conflict, ExtractOptions};
+///
+/// let a = Archive::from("-- config.toml --\ndefault = true\n-- data.txt --\nfoo\n");
+/// a.materialize_with(
+///     "testdata",
+///     ExtractOptions::new()
+///         .conflict(Conflict::Overwrite)
+///         .on_conflict(|path| match path.extension() {
+///             Some(ext) if ext == "toml" => Conflict::Skip,
+///             _ => Conflict::Overwrite,
+///         }),
+/// )
+/// .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct ExtractOptions {
+    conflict: Conflict,
+    atomic: bool,
+    portable_names: bool,
+    #[cfg(feature = "rayon")]
+    parallelism: usize,
+    #[cfg(feature = "base64")]
+    decode_base64: bool,
+    on_conflict: Option<Arc<ConflictFn>>,
+    on_progress: Option<Arc<ProgressFn>>,
+    #[cfg(unix)]
+    mode: Option<Arc<ModeFn>>,
+    #[cfg(unix)]
+    shebang_executable: bool,
+}
+
+type ConflictFn = dyn Fn(&Path) -> Conflict + Send + Sync;
+#[cfg(unix)]
+type ModeFn = dyn Fn(&str) -> Option<u32> + Send + Sync;
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            conflict: Conflict::Overwrite,
+            atomic: false,
+            portable_names: false,
+            #[cfg(feature = "rayon")]
+            parallelism: 1,
+            #[cfg(feature = "base64")]
+            decode_base64: false,
+            on_conflict: None,
+            on_progress: None,
+            #[cfg(unix)]
+            mode: None,
+            #[cfg(unix)]
+            shebang_executable: false,
+        }
+    }
+}
+
+impl ExtractOptions {
+    /// Construct a new [ExtractOptions] with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How to handle files in the archive that already exist on disk. Defaults to
+    /// [Conflict::Overwrite].
+    pub fn conflict(&mut self, conflict: Conflict) -> &mut Self {
+        self.conflict = conflict;
+        self
+    }
+
+    /// Write the files into a temporary directory alongside `dir` and only rename it into place
+    /// once every file has been written successfully.
+    ///
+    /// A failed extraction then never leaves a partially written directory behind. As a
+    /// directory can not be atomically renamed over one that already exists, this requires that
+    /// `dir` does not exist yet: an error of kind [io::ErrorKind::AlreadyExists] is returned if it
+    /// does. Defaults to `false`.
+    pub fn atomic(&mut self, atomic: bool) -> &mut Self {
+        self.atomic = atomic;
+        self
+    }
+
+    /// The number of threads to use for writing files, with `0` using the global [rayon] thread
+    /// pool.
+    ///
+    /// Any missing directories are created before writing starts so that threads never race to
+    /// create them. If the archive contains multiple files with the same name only the last of
+    /// them is written, as it would be when writing sequentially. Defaults to `1`, which writes
+    /// each file in turn on the current thread.
+    #[cfg(feature = "rayon")]
+    pub fn parallelism(&mut self, threads: usize) -> &mut Self {
+        self.parallelism = threads;
+        self
+    }
+
+    /// Decode the content of files whose names end in `.base64` and write them without the
+    /// suffix, reversing [Binary::Base64][crate::Binary::Base64].
+    ///
+    /// Content that is not valid base64 results in an error of kind
+    /// [io::ErrorKind::InvalidData] before any files are written. Defaults to `false`.
+    #[cfg(feature = "base64")]
+    pub fn decode_base64(&mut self, decode: bool) -> &mut Self {
+        self.decode_base64 = decode;
+        self
+    }
+
+    /// Reject file names that can not be written on Windows on every platform, rather than only
+    /// when running on Windows.
+    ///
+    /// This makes it possible to check that an archive can be extracted everywhere from a CI job
+    /// that does not run on Windows. See [Archive::materialize] for the names that are rejected.
+    /// Defaults to `false`.
+    pub fn portable_names(&mut self, portable: bool) -> &mut Self {
+        self.portable_names = portable;
+        self
+    }
+
+    /// Decide how to handle each file that already exists on disk by calling `f` with its path,
+    /// overriding the policy set by [ExtractOptions::conflict].
+    ///
+    /// Every existing file is checked before anything is written, so returning [Conflict::Error]
+    /// for any of them leaves the directory untouched.
+    pub fn on_conflict(
+        &mut self,
+        f: impl Fn(&Path) -> Conflict + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.on_conflict = Some(Arc::new(f));
+        self
+    }
+
+    /// Call `f` with the [Progress] of each file after it has been written.
+    ///
+    /// When writing files in parallel this is called from each of the writing threads, so the
+    /// order of the calls is not guaranteed to match the order of the files in the archive.
+    pub fn on_progress(&mut self, f: impl Fn(Progress<'_>) + Send + Sync + 'static) -> &mut Self {
+        self.on_progress = Some(Arc::new(f));
+        self
+    }
+
+    /// Set the permissions of each file after it has been written to the mode returned by `f`
+    /// for its name, leaving the default permissions in place if `f` returns `None`.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::{Archive, ExtractOptions};
+    ///
+    /// let a = Archive::from("-- run.sh --\n#!/bin/sh\necho hello\n-- data.txt --\nfoo\n");
+    /// a.materialize_with(
+    ///     "testdata",
+    ///     ExtractOptions::new().mode(|name| name.ends_with(".sh").then_some(0o755)),
+    /// )
+    /// .unwrap();
+    /// ```
+    #[cfg(unix)]
+    pub fn mode(&mut self, f: impl Fn(&str) -> Option<u32> + Send + Sync + 'static) -> &mut Self {
+        self.mode = Some(Arc::new(f));
+        self
+    }
+
+    /// Make files whose content starts with `#!` executable, by adding the execute permission
+    /// for everyone who is able to read the file.
+    ///
+    /// Files that are given a mode by [ExtractOptions::mode] are left as they are. Defaults to
+    /// `false`.
+    #[cfg(unix)]
+    pub fn shebang_executable(&mut self, executable: bool) -> &mut Self {
+        self.shebang_executable = executable;
+        self
+    }
+
+    /// Apply any configured permissions to a file that has just been written and report it as
+    /// the `index`th of `total` files to have been completed.
+    pub(crate) fn finish_file(&self, t: &Target<'_>, index: usize, total: usize) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = match self.mode.as_ref().and_then(|f| f(t.name)) {
+                Some(mode) => Some(mode),
+                None if self.shebang_executable && t.content.starts_with(b"#!") => {
+                    let mode = fs::metadata(&t.path)?.permissions().mode();
+                    Some(mode | ((mode & 0o444) >> 2))
+                }
+                None => None,
+            };
+            if let Some(mode) = mode {
+                fs::set_permissions(&t.path, fs::Permissions::from_mode(mode))?;
+            }
+        }
+
+        if let Some(f) = &self.on_progress {
+            f(Progress {
+                name: t.name,
+                index,
+                total,
+                bytes: t.content.len() as u64,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Where and what to write for the given file, relative to the target directory.
+    fn target<'a>(&self, file: &'a File) -> Result<Target<'a>, Error> {
+        #[cfg(feature = "base64")]
+        if self.decode_base64 {
+            if let Some(name) = file.name.strip_suffix(crate::binary::BASE64_SUFFIX) {
+                let content = crate::binary::decode(&file.content).map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid base64 content in {:?}: {e}", file.name),
+                    )
+                })?;
+
+                return Ok(Target {
+                    name: &file.name,
+                    path: self.target_path(name)?,
+                    content: Cow::Owned(content),
+                });
+            }
+        }
+
+        Ok(Target {
+            name: &file.name,
+            path: self.target_path(&file.name)?,
+            content: Cow::Borrowed(file.content.as_bytes()),
+        })
+    }
+
+    fn target_path(&self, name: &str) -> Result<PathBuf, Error> {
+        if self.portable_names {
+            check_windows_name(name)?;
+        }
+
+        Ok(safe_relative_path(name)?.to_path_buf())
+    }
+
+    fn conflict_for(&self, path: &Path) -> Conflict {
+        match &self.on_conflict {
+            Some(f) => f(path),
+            None => self.conflict,
+        }
+    }
+}
+
+impl fmt::Debug for ExtractOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("ExtractOptions");
+        d.field("conflict", &self.conflict)
+            .field("atomic", &self.atomic);
+        #[cfg(feature = "rayon")]
+        d.field("parallelism", &self.parallelism);
+        #[cfg(feature = "base64")]
+        d.field("decode_base64", &self.decode_base64);
+        d.field("on_conflict", &self.on_conflict.is_some())
+            .field("on_progress", &self.on_progress.is_some());
+        #[cfg(unix)]
+        d.field("mode", &self.mode.is_some())
+            .field("shebang_executable", &self.shebang_executable);
+        d.finish()
+    }
+}
+
+/// A file from an archive that is ready to be written to disk.
+pub(crate) struct Target<'a> {
+    /// The name of the file within the archive
+    pub(crate) name: &'a str,
+    pub(crate) path: PathBuf,
+    pub(crate) content: Cow<'a, [u8]>,
+}
+
+/// A summary of the changes made to disk when extracting an archive.
+///
+/// ## Example
+/// ```no_run
+/// use simple_txtar::Archive;
+///
+/// let a = Archive::from("-- a.txt --\nfoo\n-- nested/b.txt --\nbar\n");
+/// let report = a.materialize("testdata").unwrap();
+///
+/// println!(
+///     "wrote {} files ({} bytes), created {} directories",
+///     report.files_written, report.bytes_written, report.dirs_created
+/// );
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExtractReport {
+    /// The number of files that were written
+    pub files_written: usize,
+    /// The total number of bytes of file content that were written
+    pub bytes_written: u64,
+    /// The number of directories that were created, including the target directory itself
+    pub dirs_created: usize,
+    /// The paths of existing files that were left as they were due to [Conflict::Skip]
+    pub skipped: Vec<PathBuf>,
+}
+
+impl ExtractReport {
+    fn write_file(&mut self, path: &Path, content: &[u8]) -> io::Result<()> {
+        self.create_parent_dirs(path)?;
+        fs::write(path, content)?;
+        self.files_written += 1;
+        self.bytes_written += content.len() as u64;
+
+        Ok(())
+    }
+
+    pub(crate) fn create_parent_dirs(&mut self, path: &Path) -> io::Result<()> {
+        let missing: Vec<&Path> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|p| !p.as_os_str().is_empty() && !p.is_dir())
+            .collect();
+
+        for dir in missing.into_iter().rev() {
+            match fs::create_dir(dir) {
+                Ok(()) => self.dirs_created += 1,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists && dir.is_dir() => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A file in an archive that already exists on disk, as found by [Archive::check_conflicts].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileConflict {
+    /// The name of the file within the archive
+    pub name: String,
+    /// The path of the existing file
+    pub path: PathBuf,
+    /// Whether the existing content differs from the content in the archive, which is always
+    /// the case if the path is not a regular file
+    pub differs: bool,
+}
+
+impl Archive {
+    /// Find the files in this archive that already exist under the given directory and would be
+    /// overwritten by [Archive::materialize], without writing anything.
+    ///
+    /// File names are validated in the same way as [Archive::materialize]. Conflicts are
+    /// returned in the order that the files appear in the archive.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from_file("fixtures.txtar").unwrap();
+    /// let modified: Vec<_> = a
+    ///     .check_conflicts("testdata")
+    ///     .unwrap()
+    ///     .into_iter()
+    ///     .filter(|c| c.differs)
+    ///     .collect();
+    ///
+    /// if !modified.is_empty() {
+    ///     panic!("refusing to overwrite local changes: {modified:?}");
+    /// }
+    /// a.materialize("testdata").unwrap();
+    /// ```
+    pub fn check_conflicts(&self, dir: impl AsRef<Path>) -> Result<Vec<FileConflict>, Error> {
+        let dir = dir.as_ref();
+        let mut conflicts = Vec::new();
+
+        for file in self.files.iter() {
+            let path = dir.join(safe_relative_path(&file.name)?);
+            if !path.try_exists()? {
+                continue;
+            }
+            let differs = !path.is_file() || fs::read(&path)? != file.content.as_bytes();
+            conflicts.push(FileConflict {
+                name: file.name.clone(),
+                path,
+                differs,
+            });
+        }
+
+        Ok(conflicts)
+    }
+
+    /// Write each [File][crate::File] in this archive to disk under the given directory, using
+    /// the file names as paths relative to `dir`.
+    ///
+    /// Any missing parent directories (including `dir` itself) are created as needed and existing
+    /// files are overwritten. File names are validated before anything is written: absolute paths
+    /// and names containing `..` components are rejected with [Error::InvalidName] so that an
+    /// untrusted archive is unable to write outside of `dir`. On success an [ExtractReport] is
+    /// returned describing what was written.
+    ///
+    /// On Windows, names are also rejected if any of their components are a reserved device
+    /// name such as `CON` or `NUL` (with or without an extension), end in a `.` or a space, or
+    /// contain one of `<>:"|?*` or a control character. Windows treats `\` as a separator so
+    /// it can be used in place of `/`.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("-- a.txt --\nfoo\n-- nested/b.txt --\nbar");
+    /// a.materialize("testdata").unwrap();
+    ///
+    /// assert!(Archive::from("-- ../escape.txt --\nfoo").materialize("testdata").is_err());
+    /// ```
+    pub fn materialize(&self, dir: impl AsRef<Path>) -> Result<ExtractReport, Error> {
+        self.materialize_with(dir, &ExtractOptions::default())
+    }
+
+    /// Write each [File][crate::File] in this archive to disk under the given directory using
+    /// the provided [ExtractOptions].
+    ///
+    /// This behaves in the same way as [Archive::materialize] other than files that already
+    /// exist being handled as configured by `opts`. If any existing file results in
+    /// [Conflict::Error] then an error of kind [io::ErrorKind::AlreadyExists] is returned before
+    /// any files are written.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::{Archive, Conflict, ExtractOptions};
+    ///
+    /// let a = Archive::from("-- a.txt --\nfoo\n");
+    /// a.materialize_with("testdata", ExtractOptions::new().conflict(Conflict::Skip))
+    ///     .unwrap();
+    /// ```
+    pub fn materialize_with(
+        &self,
+        dir: impl AsRef<Path>,
+        opts: &ExtractOptions,
+    ) -> Result<ExtractReport, Error> {
+        let dir = dir.as_ref();
+        let targets = self
+            .files
+            .iter()
+            .map(|f| opts.target(f))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if !opts.atomic {
+            return write_targets(dir, targets, opts, ExtractReport::default());
+        }
+
+        if dir.try_exists()? {
+            return Err(already_exists(dir));
+        }
+        let mut report = ExtractReport::default();
+        report.create_parent_dirs(dir)?;
+
+        let staging = temp_sibling(dir)?;
+        fs::create_dir(&staging)?;
+        report.dirs_created += 1;
+        let res = write_targets(&staging, targets, opts, report).and_then(|report| {
+            fs::rename(&staging, dir)?;
+            Ok(report)
+        });
+        if res.is_err() {
+            let _ = fs::remove_dir_all(&staging);
+        }
+
+        res
+    }
+}
+
+fn write_targets(
+    dir: &Path,
+    targets: Vec<Target<'_>>,
+    opts: &ExtractOptions,
+    mut report: ExtractReport,
+) -> Result<ExtractReport, Error> {
+    let mut to_write = Vec::with_capacity(targets.len());
+    for mut t in targets {
+        t.path = dir.join(&t.path);
+        if t.path.try_exists()? {
+            match opts.conflict_for(&t.path) {
+                Conflict::Overwrite => (),
+                Conflict::Skip => {
+                    report.skipped.push(t.path);
+                    continue;
+                }
+                Conflict::Error => return Err(already_exists(&t.path)),
+            }
+        }
+        to_write.push(t);
+    }
+
+    #[cfg(feature = "rayon")]
+    if opts.parallelism != 1 {
+        return crate::par::write_files(to_write, opts.parallelism, opts, report);
+    }
+
+    let total = to_write.len();
+    for (index, t) in to_write.iter().enumerate() {
+        report.write_file(&t.path, &t.content)?;
+        opts.finish_file(t, index, total)?;
+    }
+
+    Ok(report)
+}
+
+fn already_exists(path: &Path) -> Error {
+    Error::Io(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("file already exists: {}", path.display()),
+    ))
+}
+
+/// A unique path in the same directory as `path` that can be written to before being renamed
+/// over it.
+pub(crate) fn temp_sibling(path: &Path) -> io::Result<PathBuf> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("path does not have a file name: {}", path.display()),
+        )
+    })?;
+    let mut tmp = OsString::from(".");
+    tmp.push(name);
+    tmp.push(format!(
+        ".tmp-{}-{}",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    Ok(path.with_file_name(tmp))
+}
+
+/// Extract a `txtar` archive read from `r` directly to disk under the given directory, writing
+/// each file as its content is read rather than parsing the whole archive first.
+///
+/// The archive is read one line at a time so memory usage is bounded by the length of the longest
+/// line rather than the size of the archive. Files are written in the same way as
+/// [Archive::materialize], including the validation of file names, but as each name is only
+/// checked when its file marker is reached any earlier files will already have been written when
+/// an invalid name is found. The comment is discarded and file content does not need to be valid
+/// UTF-8.
+///
+/// On success an [ExtractReport] is returned describing what was written.
+///
+/// ## Example
+/// ```no_run
+/// use simple_txtar::extract_from_reader;
+/// use std::{fs::File, io::BufReader};
+///
+/// let r = BufReader::new(File::open("corpus.txtar").unwrap());
+/// extract_from_reader(r, "testdata").unwrap();
+/// ```
+pub fn extract_from_reader(
+    mut r: impl BufRead,
+    dir: impl AsRef<Path>,
+) -> Result<ExtractReport, Error> {
+    let dir = dir.as_ref();
+    let mut report = ExtractReport::default();
+    let mut buf = Vec::new();
+    let mut current: Option<io::BufWriter<fs::File>> = None;
+    let mut needs_newline = false;
+    let mut first_line = true;
+
+    loop {
+        buf.clear();
+        if r.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+
+        let mut line = buf.as_slice();
+        if std::mem::take(&mut first_line) {
+            line = line.strip_prefix("\u{feff}".as_bytes()).unwrap_or(line);
+        }
+
+        match str::from_utf8(line).ok().and_then(marker_name) {
+            Some(name) => {
+                finish_file(current.take(), needs_newline, &mut report)?;
+                let path = dir.join(safe_relative_path(name.trim())?);
+                report.create_parent_dirs(&path)?;
+                current = Some(io::BufWriter::new(fs::File::create(path)?));
+                report.files_written += 1;
+                needs_newline = false;
+            }
+            None => {
+                if let Some(w) = current.as_mut() {
+                    w.write_all(line)?;
+                    report.bytes_written += line.len() as u64;
+                    needs_newline = !line.ends_with(b"\n");
+                }
+            }
+        }
+    }
+
+    finish_file(current, needs_newline, &mut report)?;
+
+    Ok(report)
+}
+
+fn finish_file(
+    w: Option<io::BufWriter<fs::File>>,
+    needs_newline: bool,
+    report: &mut ExtractReport,
+) -> Result<(), Error> {
+    if let Some(mut w) = w {
+        if needs_newline {
+            w.write_all(b"\n")?;
+            report.bytes_written += 1;
+        }
+        w.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Check that a file name from an archive is a non-empty relative path that stays beneath the
+/// directory it is joined on to.
+pub(crate) fn safe_relative_path(name: &str) -> Result<&Path, Error> {
+    #[cfg(windows)]
+    check_windows_name(name)?;
+
+    let path = Path::new(name);
+    let mut has_normal = false;
+
+    for component in path.components() {
+        match component {
+            Component::Normal(_) => has_normal = true,
+            Component::CurDir => (),
+            Component::ParentDir => return Err(invalid_name(name, "contains a '..' component")),
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(invalid_name(name, "is an absolute path"))
+            }
+        }
+    }
+
+    if !has_normal {
+        return Err(invalid_name(name, "does not name a file"));
+    }
+
+    Ok(path)
+}
+
+/// Check that every component of a file name can be used as a file name on Windows.
+fn check_windows_name(name: &str) -> Result<(), Error> {
+    const RESERVED: [&str; 4] = ["CON", "PRN", "AUX", "NUL"];
+
+    let components = match cfg!(windows) {
+        true => name.split(['/', '\\']).collect::<Vec<_>>(),
+        false => name.split('/').collect(),
+    };
+
+    for component in components {
+        if matches!(component, "" | "." | "..") {
+            continue;
+        }
+
+        let stem = component.split('.').next().unwrap_or_default().trim_end();
+        let numbered = |prefix: &str| {
+            stem.len() == 4
+                && stem[..3].eq_ignore_ascii_case(prefix)
+                && matches!(stem.as_bytes()[3], b'1'..=b'9')
+        };
+        if RESERVED.iter().any(|r| stem.eq_ignore_ascii_case(r))
+            || numbered("COM")
+            || numbered("LPT")
+        {
+            return Err(invalid_name(
+                name,
+                "contains a reserved Windows device name",
+            ));
+        }
+
+        if component.ends_with(['.', ' ']) {
+            return Err(invalid_name(name, "has a component ending in '.' or ' '"));
+        }
+
+        if component
+            .chars()
+            .any(|c| c.is_ascii_control() || "<>:\"|?*\\".contains(c))
+        {
+            return Err(invalid_name(
+                name,
+                "contains a character not allowed on Windows",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn invalid_name(name: &str, reason: &'static str) -> Error {
+    Error::InvalidName {
+        name: name.to_string(),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn materialize_writes_nested_files() {
+        let tmp = TestDir::new("materialize");
+        let dir = tmp.join("out");
+        let a = Archive::from("-- a.txt --\nfoo\n-- ./nested/deeper/b.txt --\nbar");
+
+        let report = a.materialize(&dir).unwrap();
+        let a_txt = fs::read_to_string(dir.join("a.txt")).unwrap();
+        let b_txt = fs::read_to_string(dir.join("nested/deeper/b.txt")).unwrap();
+
+        assert_eq!(a_txt, "foo\n");
+        assert_eq!(b_txt, "bar\n");
+        assert_eq!(
+            report,
+            ExtractReport {
+                files_written: 2,
+                bytes_written: 8,
+                dirs_created: 3,
+                skipped: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn materialize_rejects_escaping_names_before_writing() {
+        let tmp = TestDir::new("materialize-escape");
+        let dir = tmp.join("out");
+
+        for name in ["../up.txt", "a/../../up.txt", "/abs.txt", "", "."] {
+            let a = Archive::builder()
+                .file(("ok.txt", "fine\n"))
+                .file((name, "bad\n"))
+                .build();
+            let err = a.materialize(&dir).unwrap_err();
+            assert!(matches!(err, Error::InvalidName { .. }), "{name:?}");
+        }
+
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn conflicts_follow_the_configured_policy() {
+        let dir = TestDir::new("conflict");
+        Archive::from("-- a.txt --\nold\n-- b.txt --\nold\n")
+            .materialize(&dir)
+            .unwrap();
+        let a = Archive::from("-- a.txt --\nnew\n-- b.txt --\nnew\n-- c.txt --\nnew\n");
+
+        let err = a
+            .materialize_with(&dir, ExtractOptions::new().conflict(Conflict::Error))
+            .unwrap_err();
+        assert!(matches!(err, Error::Io(e) if e.kind() == io::ErrorKind::AlreadyExists));
+        assert!(!dir.join("c.txt").exists());
+
+        let report = a
+            .materialize_with(
+                &dir,
+                ExtractOptions::new().on_conflict(|p| match p.ends_with("a.txt") {
+                    true => Conflict::Skip,
+                    false => Conflict::Overwrite,
+                }),
+            )
+            .unwrap();
+        let res = Archive::from_dir(&dir);
+
+        assert_eq!(report.skipped, vec![dir.join("a.txt")]);
+        assert_eq!(
+            res.unwrap().to_string(),
+            "-- a.txt --\nold\n-- b.txt --\nnew\n-- c.txt --\nnew\n"
+        );
+    }
+
+    #[test]
+    fn check_conflicts_compares_existing_content() {
+        let dir = TestDir::new("check-conflicts");
+        Archive::from("-- a --\nsame\n-- b --\nlocal change\n-- c/d --\n")
+            .materialize(&dir)
+            .unwrap();
+
+        let a = Archive::from("-- a --\nsame\n-- b --\noriginal\n-- c --\n-- e --\n");
+        let res = a.check_conflicts(&dir);
+
+        let found: Vec<_> = res
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.name, c.differs))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("a".to_string(), false),
+                ("b".to_string(), true),
+                ("c".to_string(), true),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn mode_is_applied_to_written_files() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TestDir::new("mode");
+        let a =
+            Archive::from("-- run.sh --\n#!/bin/sh\n-- build --\n#!/bin/sh\n-- data.txt --\nfoo\n");
+        a.materialize_with(
+            &dir,
+            ExtractOptions::new()
+                .mode(|name| name.ends_with(".sh").then_some(0o750))
+                .shebang_executable(true),
+        )
+        .unwrap();
+        let mode = |name| fs::metadata(dir.join(name)).unwrap().permissions().mode() & 0o777;
+        let (script, build, data) = (mode("run.sh"), mode("build"), mode("data.txt"));
+
+        assert_eq!(script, 0o750);
+        assert_eq!(build & 0o100, 0o100);
+        assert_eq!(data & 0o111, 0);
+    }
+
+    #[test]
+    fn failed_atomic_extraction_leaves_nothing_behind() {
+        let tmp = TestDir::new("atomic");
+        let dir = tmp.join("out");
+        let mut opts = ExtractOptions::new();
+        opts.atomic(true);
+
+        let a = Archive::from("-- a.txt --\nfoo\n-- a.txt/b.txt --\nbar\n");
+        assert!(a.materialize_with(&dir, &opts).is_err());
+        assert!(!dir.exists());
+
+        let a = Archive::from("-- a.txt --\nfoo\n-- b/c.txt --\nbar\n");
+        let report = a.materialize_with(&dir, &opts).unwrap();
+        let res = Archive::from_dir(&dir);
+        let again = a.materialize_with(&dir, &opts);
+
+        assert_eq!(report.dirs_created, 2);
+        assert_eq!(res.unwrap(), a);
+        assert!(again.is_err());
+    }
+
+    #[test]
+    fn atomic_extraction_of_an_empty_archive_creates_the_directory() {
+        let tmp = TestDir::new("atomic-empty");
+        let dir = tmp.join("out");
+        let mut opts = ExtractOptions::new();
+        opts.atomic(true);
+
+        let report = Archive::default().materialize_with(&dir, &opts);
+        let is_dir = dir.is_dir();
+
+        assert_eq!(report.unwrap().dirs_created, 1);
+        assert!(is_dir);
+    }
+
+    #[test]
+    fn portable_names_reject_windows_only_problems() {
+        let tmp = TestDir::new("portable");
+        let dir = tmp.join("out");
+        let mut opts = ExtractOptions::new();
+        opts.portable_names(true);
+
+        for name in [
+            "nul",
+            "a/Com1.txt",
+            "lpt9.tar.gz",
+            "a./b",
+            "b /c",
+            "c:d",
+            "e\\f",
+            "g?",
+        ] {
+            let err = Archive::from(format!("-- ok --\n-- {name} --\n"))
+                .materialize_with(&dir, &opts)
+                .unwrap_err();
+            assert!(matches!(err, Error::InvalidName { .. }), "{name:?}");
+        }
+        assert!(!dir.exists());
+
+        let a = Archive::from("-- cons/console --\n-- com10 --\n-- a.b.c --\n");
+        let res = a.materialize_with(&dir, &opts);
+        assert_eq!(res.unwrap().files_written, 3);
+    }
+
+    #[test]
+    fn extract_from_reader_matches_materialize() {
+        let dir = TestDir::new("reader");
+        let s = "\u{feff}comment\n-- a.txt --\nfoo\n-- b/c.txt --\r\nbar\r\n-- d.txt --\nbaz";
+
+        extract_from_reader(s.as_bytes(), &dir).unwrap();
+        let res = Archive::from_dir(&dir);
+
+        let mut expected = Archive::from(s);
+        expected.set_comment("");
+        assert_eq!(res.unwrap(), expected);
+    }
+}
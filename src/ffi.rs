@@ -0,0 +1,280 @@
+//! A C API for embedding this implementation of `txtar` in other languages.
+//!
+//! Archives are exposed as opaque pointers that are created by [txtar_new] or [txtar_parse] and
+//! must be released with [txtar_free]. Strings are passed as a pointer and a length rather than
+//! being NUL terminated, and strings returned by the API borrow from the archive they were
+//! obtained from, remaining valid until the archive is modified or freed. Building the crate as a
+//! `cdylib` with the `ffi` feature produces a shared library exporting the following functions:
+//!
+//! ```sh
+//! cargo rustc --release --features ffi --crate-type cdylib
+//! ```
+//!
+//!
+//! ```c
+//! #include <stdbool.h>
+//! #include <stddef.h>
+//! #include <stdint.h>
+//!
+//! typedef struct TxtarArchive TxtarArchive;
+//! typedef struct { const uint8_t *ptr; size_t len; } TxtarStr;
+//!
+//! TxtarArchive *txtar_new(void);
+//! TxtarArchive *txtar_parse(const uint8_t *buf, size_t len);
+//! void txtar_free(TxtarArchive *archive);
+//! size_t txtar_len(const TxtarArchive *archive);
+//! TxtarStr txtar_comment(const TxtarArchive *archive);
+//! bool txtar_file(const TxtarArchive *archive, size_t index, TxtarStr *name, TxtarStr *content);
+//! bool txtar_get(const TxtarArchive *archive, const uint8_t *name, size_t name_len, TxtarStr *content);
+//! bool txtar_add_file(TxtarArchive *archive, const uint8_t *name, size_t name_len,
+//!                     const uint8_t *content, size_t content_len);
+//! size_t txtar_format(const TxtarArchive *archive, uint8_t *buf, size_t cap);
+//! ```
+//!
+//! ## Example
+//! ```c
+//! const char *s = "comment\n-- a.txt --\nfoo\n";
+//! TxtarArchive *a = txtar_parse((const uint8_t *)s, strlen(s));
+//!
+//! TxtarStr name, content;
+//! for (size_t i = 0; txtar_file(a, i, &name, &content); i++) {
+//!     printf("%.*s: %zu bytes\n", (int)name.len, name.ptr, content.len);
+//! }
+//!
+//! size_t len = txtar_format(a, NULL, 0);
+//! uint8_t *buf = malloc(len);
+//! txtar_format(a, buf, len);
+//!
+//! free(buf);
+//! txtar_free(a);
+//! ```
+use crate::{Archive, File};
+use std::{ptr, slice, str};
+
+/// A borrowed UTF-8 string that is not NUL terminated.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct TxtarStr {
+    /// A pointer to the first byte of the string
+    pub ptr: *const u8,
+    /// The length of the string in bytes
+    pub len: usize,
+}
+
+impl From<&str> for TxtarStr {
+    fn from(s: &str) -> Self {
+        Self {
+            ptr: s.as_ptr(),
+            len: s.len(),
+        }
+    }
+}
+
+/// Create a new, empty archive.
+#[no_mangle]
+pub extern "C" fn txtar_new() -> *mut Archive {
+    Box::into_raw(Box::default())
+}
+
+/// Parse an archive from `len` bytes of UTF-8 text starting at `buf`, returning NULL if the text
+/// is not valid UTF-8.
+///
+/// # Safety
+/// `buf` must point to at least `len` readable bytes, or may be NULL if `len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn txtar_parse(buf: *const u8, len: usize) -> *mut Archive {
+    // SAFETY: upheld by the caller
+    let bytes = unsafe { bytes(buf, len) };
+    match str::from_utf8(bytes) {
+        Ok(s) => Box::into_raw(Box::new(Archive::from(s))),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Free an archive created by [txtar_new] or [txtar_parse]. Passing NULL does nothing.
+///
+/// # Safety
+/// `archive` must be NULL or a pointer returned by [txtar_new] or [txtar_parse] that has not
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn txtar_free(archive: *mut Archive) {
+    if !archive.is_null() {
+        // SAFETY: the pointer was created by Box::into_raw and has not been freed
+        drop(unsafe { Box::from_raw(archive) });
+    }
+}
+
+/// The number of files in the archive.
+///
+/// # Safety
+/// `archive` must be a valid pointer to an archive.
+#[no_mangle]
+pub unsafe extern "C" fn txtar_len(archive: *const Archive) -> usize {
+    // SAFETY: upheld by the caller
+    unsafe { &*archive }.len()
+}
+
+/// The comment at the top of the archive.
+///
+/// # Safety
+/// `archive` must be a valid pointer to an archive.
+#[no_mangle]
+pub unsafe extern "C" fn txtar_comment(archive: *const Archive) -> TxtarStr {
+    // SAFETY: upheld by the caller
+    TxtarStr::from(unsafe { &*archive }.comment())
+}
+
+/// Write the name and content of the file at `index` to `name` and `content`, returning `false`
+/// if `index` is out of bounds. This can be used to iterate over the files in the archive.
+///
+/// # Safety
+/// `archive` must be a valid pointer to an archive and `name` and `content` must be valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn txtar_file(
+    archive: *const Archive,
+    index: usize,
+    name: *mut TxtarStr,
+    content: *mut TxtarStr,
+) -> bool {
+    // SAFETY: upheld by the caller
+    let Some(f) = unsafe { &*archive }.files.get(index) else {
+        return false;
+    };
+
+    // SAFETY: upheld by the caller
+    unsafe {
+        name.write(TxtarStr::from(f.name.as_str()));
+        content.write(TxtarStr::from(f.content.as_str()));
+    }
+
+    true
+}
+
+/// Write the content of the first file with the given name to `content`, returning `false` if
+/// there is no such file.
+///
+/// # Safety
+/// `archive` must be a valid pointer to an archive, `name` must point to at least `name_len`
+/// readable bytes and `content` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn txtar_get(
+    archive: *const Archive,
+    name: *const u8,
+    name_len: usize,
+    content: *mut TxtarStr,
+) -> bool {
+    // SAFETY: upheld by the caller
+    let Ok(name) = str::from_utf8(unsafe { bytes(name, name_len) }) else {
+        return false;
+    };
+    // SAFETY: upheld by the caller
+    let Some(f) = unsafe { &*archive }.get(name) else {
+        return false;
+    };
+
+    // SAFETY: upheld by the caller
+    unsafe { content.write(TxtarStr::from(f.content.as_str())) };
+
+    true
+}
+
+/// Add a file to the end of the archive, returning `false` without modifying the archive if
+/// either the name or content are not valid UTF-8.
+///
+/// # Safety
+/// `archive` must be a valid pointer to an archive, `name` must point to at least `name_len`
+/// readable bytes and `content` must point to at least `content_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn txtar_add_file(
+    archive: *mut Archive,
+    name: *const u8,
+    name_len: usize,
+    content: *const u8,
+    content_len: usize,
+) -> bool {
+    // SAFETY: upheld by the caller
+    let (name, content) = unsafe { (bytes(name, name_len), bytes(content, content_len)) };
+    let (Ok(name), Ok(content)) = (str::from_utf8(name), str::from_utf8(content)) else {
+        return false;
+    };
+
+    // SAFETY: upheld by the caller
+    unsafe { &mut *archive }.add_file(File::new(name, content));
+
+    true
+}
+
+/// Format the archive as `txtar` text, returning its length in bytes.
+///
+/// The text is only written to `buf` if `cap` is large enough to hold all of it, so the required
+/// size can be found by first calling this with a `cap` of zero. The output is not NUL
+/// terminated.
+///
+/// # Safety
+/// `archive` must be a valid pointer to an archive and `buf` must be valid for writes of `cap`
+/// bytes, or may be NULL if `cap` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn txtar_format(archive: *const Archive, buf: *mut u8, cap: usize) -> usize {
+    // SAFETY: upheld by the caller
+    let s = unsafe { &*archive }.canonical_string();
+    if s.len() <= cap && !buf.is_null() {
+        // SAFETY: buf is valid for cap bytes, which is at least s.len(), and can not overlap
+        // with the newly allocated string
+        unsafe { ptr::copy_nonoverlapping(s.as_ptr(), buf, s.len()) };
+    }
+
+    s.len()
+}
+
+/// # Safety
+/// `ptr` must point to at least `len` readable bytes that outlive `'a`, or may be NULL if `len`
+/// is zero.
+unsafe fn bytes<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        return &[];
+    }
+
+    // SAFETY: upheld by the caller
+    unsafe { slice::from_raw_parts(ptr, len) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_str<'a>(s: TxtarStr) -> &'a str {
+        // SAFETY: strings returned by the API point to valid UTF-8
+        unsafe { str::from_utf8_unchecked(bytes(s.ptr, s.len)) }
+    }
+
+    #[test]
+    fn c_api_round_trips() {
+        let s = "comment\n-- a.txt --\nfoo\n";
+        let empty = TxtarStr::from("");
+        let (mut name, mut content) = (empty, empty);
+
+        // SAFETY: all pointers passed are valid for the duration of each call
+        unsafe {
+            let a = txtar_parse(s.as_ptr(), s.len());
+            assert!(txtar_add_file(a, b"b".as_ptr(), 1, ptr::null(), 0));
+            assert!(!txtar_add_file(a, [0xff].as_ptr(), 1, ptr::null(), 0));
+
+            assert_eq!(txtar_len(a), 2);
+            assert_eq!(to_str(txtar_comment(a)), "comment\n");
+            assert!(txtar_file(a, 1, &mut name, &mut content));
+            assert_eq!((to_str(name), to_str(content)), ("b", ""));
+            assert!(!txtar_file(a, 2, &mut name, &mut content));
+            assert!(txtar_get(a, b"a.txt".as_ptr(), 5, &mut content));
+            assert_eq!(to_str(content), "foo\n");
+
+            let len = txtar_format(a, ptr::null_mut(), 0);
+            let mut buf = vec![0; len];
+            assert_eq!(txtar_format(a, buf.as_mut_ptr(), buf.len()), len);
+            assert_eq!(buf, b"comment\n-- a.txt --\nfoo\n-- b --\n");
+
+            txtar_free(a);
+            assert!(txtar_parse([0xff].as_ptr(), 1).is_null());
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Skipping ignored paths when reading a directory into an archive.
+use crate::PackOptions;
+use std::{io, path::Path};
+
+#[cfg(feature = "ignore")]
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+/// The ignore rules that apply to the directory currently being read by
+/// [Archive::from_dir_with][crate::Archive::from_dir_with].
+#[derive(Debug, Default)]
+pub(crate) struct Filter {
+    /// Rules built from the patterns supplied in the [PackOptions]
+    #[cfg(feature = "ignore")]
+    patterns: Option<Gitignore>,
+    /// Rules from each `.gitignore` file between the root and the current directory, from
+    /// shallowest to deepest
+    #[cfg(feature = "ignore")]
+    gitignores: Option<Vec<Gitignore>>,
+}
+
+#[cfg(not(feature = "ignore"))]
+impl Filter {
+    pub(crate) fn new(_: &Path, _: &PackOptions) -> io::Result<Self> {
+        Ok(Self::default())
+    }
+
+    pub(crate) fn is_ignored(&self, _: &Path, _: bool) -> bool {
+        false
+    }
+
+    pub(crate) fn enter(&mut self, _: &Path) {}
+
+    pub(crate) fn leave(&mut self) {}
+}
+
+#[cfg(feature = "ignore")]
+impl Filter {
+    pub(crate) fn new(root: &Path, opts: &PackOptions) -> io::Result<Self> {
+        let patterns = match opts.ignore.is_empty() {
+            true => None,
+            false => {
+                let mut builder = GitignoreBuilder::new(root);
+                for pattern in opts.ignore.iter() {
+                    builder.add_line(None, pattern).map_err(invalid_pattern)?;
+                }
+                Some(builder.build().map_err(invalid_pattern)?)
+            }
+        };
+
+        Ok(Self {
+            patterns,
+            gitignores: opts.gitignore.then(Vec::new),
+        })
+    }
+
+    /// Whether `path` is ignored, with user supplied patterns taking priority over any
+    /// `.gitignore` files and deeper `.gitignore` files taking priority over shallower ones.
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let gitignores = self.gitignores.iter().flat_map(|g| g.iter().rev());
+
+        for rules in self.patterns.iter().chain(gitignores) {
+            let m = rules.matched(path, is_dir);
+            if !m.is_none() {
+                return m.is_ignore();
+            }
+        }
+
+        false
+    }
+
+    /// Load the `.gitignore` file for a directory that is about to be read, if there is one.
+    pub(crate) fn enter(&mut self, dir: &Path) {
+        if let Some(gitignores) = self.gitignores.as_mut() {
+            // Unreadable or invalid lines are skipped in the same way that git skips them.
+            let (rules, _) = Gitignore::new(dir.join(".gitignore"));
+            gitignores.push(rules);
+        }
+    }
+
+    /// Drop the `.gitignore` file of a directory that has been fully read.
+    pub(crate) fn leave(&mut self) {
+        if let Some(gitignores) = self.gitignores.as_mut() {
+            gitignores.pop();
+        }
+    }
+}
+
+#[cfg(feature = "ignore")]
+fn invalid_pattern(e: ignore::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, e)
+}
@@ -0,0 +1,276 @@
+//! Golden file testing: checking that a directory matches the files in an [Archive].
+use crate::{diff::unified_diff, Archive, ByteArchive, DirDiff, SyncReport, WriteOptions};
+use std::{collections::HashMap, env, fmt, io, path::Path};
+
+/// The environment variable that switches [Archive::assert_golden] from checking the golden
+/// archive to updating it.
+const UPDATE_GOLDEN: &str = "UPDATE_GOLDEN";
+
+/// The result of comparing an [Archive] with a directory using [Archive::compare_dir].
+///
+/// The [Display][fmt::Display] implementation renders a unified diff of every file that differs,
+/// from the content in the archive to the content on disk.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DirComparison {
+    /// The names of the files that differ between the archive and the directory
+    pub diff: DirDiff,
+    /// A unified diff for each file that differs, in the same order as the names in `diff`
+    pub file_diffs: Vec<FileDiff>,
+}
+
+impl DirComparison {
+    /// Whether or not the archive and directory have the same contents.
+    pub fn is_match(&self) -> bool {
+        self.diff.is_empty()
+    }
+}
+
+impl fmt::Display for DirComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for d in self.file_diffs.iter() {
+            write!(f, "{d}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A readable description of how a single file differs between an [Archive] and a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    /// The name of the file
+    pub name: String,
+    /// A unified diff from the content in the archive to the content on disk, with a file that
+    /// is missing from one side being treated as empty. Files on disk that are not valid UTF-8
+    /// are described rather than diffed.
+    pub unified: String,
+}
+
+impl fmt::Display for FileDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.unified)
+    }
+}
+
+impl Archive {
+    /// Compare the files in this archive with the files under the given directory, producing a
+    /// unified diff for each file that differs.
+    ///
+    /// Files are matched up in the same way as [Archive::diff_dir], with the diffs for files
+    /// missing from the directory coming first, followed by files that are missing from the
+    /// archive and then files whose content differs.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let expected = Archive::from_file("testdata/golden.txtar").unwrap();
+    /// let cmp = expected.compare_dir("target/output").unwrap();
+    ///
+    /// if !cmp.is_match() {
+    ///     eprintln!("{cmp}");
+    /// }
+    /// ```
+    pub fn compare_dir(&self, dir: impl AsRef<Path>) -> io::Result<DirComparison> {
+        let disk = ByteArchive::from_dir(dir)?;
+        let diff = self.diff_against(&disk);
+        let on_disk: HashMap<&[u8], &[u8]> = disk
+            .iter()
+            .map(|f| (f.name.as_slice(), f.content.as_slice()))
+            .collect();
+        let disk_content = |name: &str| on_disk.get(name.as_bytes()).copied().unwrap_or_default();
+        let archive_content = |name: &str| self.get(name).map_or("", |f| f.content.as_str());
+
+        let file_diffs = diff
+            .missing_on_disk
+            .iter()
+            .chain(diff.missing_in_archive.iter())
+            .chain(diff.content_differs.iter())
+            .map(|name| {
+                let a_name = match diff.missing_in_archive.contains(name) {
+                    true => "/dev/null".to_string(),
+                    false => format!("a/{name}"),
+                };
+                let b_name = match diff.missing_on_disk.contains(name) {
+                    true => "/dev/null".to_string(),
+                    false => format!("b/{name}"),
+                };
+                let unified = match std::str::from_utf8(disk_content(name)) {
+                    Ok(content) => unified_diff(&a_name, &b_name, archive_content(name), content),
+                    Err(_) => format!("Binary files {a_name} and {b_name} differ\n"),
+                };
+
+                FileDiff {
+                    name: name.clone(),
+                    unified,
+                }
+            })
+            .collect();
+
+        Ok(DirComparison { diff, file_diffs })
+    }
+
+    /// Assert that the files under the given directory match the files in this archive,
+    /// panicking with a unified diff of every file that differs if they do not.
+    ///
+    /// This will also panic if the directory can not be read. See [Archive::compare_dir] for a
+    /// non-panicking version.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let expected = Archive::from_file("testdata/golden.txtar").unwrap();
+    /// // ... run the code under test, writing its output to target/output
+    /// expected.assert_matches_dir("target/output");
+    /// ```
+    #[track_caller]
+    pub fn assert_matches_dir(&self, dir: impl AsRef<Path>) {
+        let dir = dir.as_ref();
+        match self.compare_dir(dir) {
+            Ok(cmp) if cmp.is_match() => (),
+            Ok(cmp) => panic!("directory {dir:?} does not match the archive:\n{cmp}"),
+            Err(e) => panic!("unable to read directory {dir:?}: {e}"),
+        }
+    }
+}
+
+impl Archive {
+    /// Check that the files under `dir` match the golden archive at `golden`, or update the
+    /// golden archive to match them if the `UPDATE_GOLDEN` environment variable is set.
+    ///
+    /// When checking, this panics with a unified diff of every file that differs, in the same
+    /// way as [Archive::assert_matches_dir], along with a reminder of how to update the golden
+    /// archive. Setting `UPDATE_GOLDEN` to anything other than an empty string or `0` instead
+    /// updates the golden archive using [Archive::update_golden] so that the usual workflow is to
+    /// run the tests once with `UPDATE_GOLDEN=1`, review the diff of the golden archives and then
+    /// commit them.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// // ... run the code under test, writing its output to target/output
+    /// Archive::assert_golden("testdata/output.txtar", "target/output");
+    /// ```
+    #[track_caller]
+    pub fn assert_golden(golden: impl AsRef<Path>, dir: impl AsRef<Path>) {
+        let (golden, dir) = (golden.as_ref(), dir.as_ref());
+        let update = env::var_os(UPDATE_GOLDEN).is_some_and(|v| !v.is_empty() && v != "0");
+        if update {
+            if let Err(e) = Archive::update_golden(golden, dir) {
+                panic!("unable to update golden archive {golden:?}: {e}");
+            }
+            return;
+        }
+
+        let cmp = Archive::from_file(golden).and_then(|a| a.compare_dir(dir));
+        match cmp {
+            Ok(cmp) if cmp.is_match() => (),
+            Ok(cmp) => panic!(
+                "directory {dir:?} does not match golden archive {golden:?}:\n{cmp}\n\
+                 run with {UPDATE_GOLDEN}=1 to update the golden archive"
+            ),
+            Err(e) => panic!(
+                "unable to compare {dir:?} with golden archive {golden:?}: {e}\n\
+                 run with {UPDATE_GOLDEN}=1 to create or update the golden archive"
+            ),
+        }
+    }
+
+    /// Update the golden archive at `golden` to match the files under `dir`, creating it if it
+    /// does not exist.
+    ///
+    /// The archive is updated using [Archive::sync_from_dir] so the comment and the order of
+    /// existing files are preserved, keeping the diff of the golden archive to a minimum. The
+    /// file is only written if something has changed, in which case it is replaced atomically.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let report = Archive::update_golden("testdata/output.txtar", "target/output").unwrap();
+    /// println!("added {:?}", report.added);
+    /// ```
+    pub fn update_golden(
+        golden: impl AsRef<Path>,
+        dir: impl AsRef<Path>,
+    ) -> io::Result<SyncReport> {
+        let golden = golden.as_ref();
+        let (mut a, exists) = match Archive::from_file(golden) {
+            Ok(a) => (a, true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (Archive::default(), false),
+            Err(e) => return Err(e),
+        };
+
+        let report = a.sync_from_dir(dir)?;
+        if !exists || !report.is_empty() {
+            a.to_file_with(golden, WriteOptions::new().atomic(true))?;
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+    use std::fs;
+
+    #[test]
+    fn comparison_includes_unified_diffs() {
+        let dir = TestDir::new("golden");
+        Archive::from("-- a --\nsame\nnew\n-- c --\nextra\n")
+            .materialize(&dir)
+            .unwrap();
+
+        let a = Archive::from("-- a --\nsame\nold\n-- b --\nmissing\n");
+        let cmp = a.compare_dir(&dir);
+        let matches =
+            std::panic::catch_unwind(|| Archive::from_dir(&dir).unwrap().assert_matches_dir(&dir));
+
+        let cmp = cmp.unwrap();
+        assert!(!cmp.is_match());
+        assert!(matches.is_ok());
+        assert_eq!(
+            cmp.to_string(),
+            "\
+--- a/b
++++ /dev/null
+@@ -1 +0,0 @@
+-missing
+--- /dev/null
++++ b/c
+@@ -0,0 +1 @@
++extra
+--- a/a
++++ b/a
+@@ -1,2 +1,2 @@
+ same
+-old
++new
+"
+        );
+    }
+
+    #[test]
+    fn update_golden_preserves_the_comment() {
+        let dir = TestDir::new("update");
+        let out = dir.join("out");
+        let golden = dir.join("golden.txtar");
+        Archive::from("-- b --\nnew\n-- a --\nsame\n")
+            .materialize(&out)
+            .unwrap();
+
+        let created = Archive::update_golden(&golden, &out);
+        fs::write(&golden, "comment\n-- b --\nold\n-- a --\nsame\n").unwrap();
+        let updated = Archive::update_golden(&golden, &out);
+        let content = fs::read_to_string(&golden);
+        Archive::assert_golden(&golden, &out);
+
+        assert_eq!(created.unwrap().added, ["a", "b"]);
+        assert_eq!(updated.unwrap().modified, ["b"]);
+        assert_eq!(content.unwrap(), "comment\n-- b --\nnew\n-- a --\nsame\n");
+    }
+}
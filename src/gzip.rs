@@ -0,0 +1,77 @@
+//! Reading and writing gzip compressed `txtar` files.
+use crate::Archive;
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+/// The first two bytes of every gzip stream.
+const MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Read the file at `path` into a string, decompressing it first if it has a `.gz` extension or
+/// begins with the gzip magic bytes.
+pub(crate) fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+    let path = path.as_ref();
+    let raw = fs::read(path)?;
+    if !raw.starts_with(&MAGIC) && path.extension().is_none_or(|ext| ext != "gz") {
+        return String::from_utf8(raw).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+    }
+
+    let mut s = String::new();
+    MultiGzDecoder::new(raw.as_slice()).read_to_string(&mut s)?;
+
+    Ok(s)
+}
+
+impl Archive {
+    /// Serialize this archive as a gzip compressed `txtar` file at the specified path, creating
+    /// the file if it does not exist and truncating it if it does.
+    ///
+    /// Compressed files are decompressed automatically by [Archive::from_file], so the path
+    /// conventionally ends in `.txtar.gz`.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("-- file1 --\nfoo");
+    /// a.to_file_gz("my_txtar_archive.txtar.gz").unwrap();
+    ///
+    /// assert_eq!(Archive::from_file("my_txtar_archive.txtar.gz").unwrap(), a);
+    /// ```
+    pub fn to_file_gz(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let f = io::BufWriter::new(fs::File::create(path)?);
+        let mut w = GzEncoder::new(f, Compression::default());
+        self.to_writer(&mut w)?;
+
+        w.finish()?.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn compressed_files_are_detected() {
+        let dir = TestDir::new("gzip");
+        let a = Archive::from("comment\n-- a.txt --\nfoo\n");
+        a.to_file_gz(dir.join("a.txtar.gz")).unwrap();
+        fs::copy(dir.join("a.txtar.gz"), dir.join("a.txtar")).unwrap();
+        a.to_file(dir.join("plain.txtar")).unwrap();
+        fs::write(dir.join("bad.gz"), "not gzip").unwrap();
+
+        let by_ext = Archive::from_file(dir.join("a.txtar.gz"));
+        let by_magic = Archive::from_file(dir.join("a.txtar"));
+        let plain = Archive::from_file(dir.join("plain.txtar"));
+        let bad = Archive::from_file(dir.join("bad.gz"));
+
+        assert_eq!(by_ext.unwrap(), a);
+        assert_eq!(by_magic.unwrap(), a);
+        assert_eq!(plain.unwrap(), a);
+        assert!(bad.is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Rendering an [Archive] as a static HTML page.
+use crate::Archive;
+use std::collections::HashSet;
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em auto; max-width: 60em; padding: 0 1em; }
+pre { background: #f6f8fa; border: 1px solid #d0d7de; border-radius: 6px; overflow-x: auto; padding: 1em; }
+h2 { font-family: monospace; font-size: 1.1em; }
+h2 a { color: inherit; text-decoration: none; }";
+
+impl Archive {
+    /// Render this archive as a self contained HTML page.
+    ///
+    /// The page contains the comment, an index of the files linking to each of them and then a
+    /// section per file with its name as a heading followed by its content. Each section has an
+    /// anchor derived from the file name, with characters other than ASCII letters, digits, `.`,
+    /// `_`, `-` and `/` replaced by `-` and a numeric suffix added if needed to keep it unique, so
+    /// individual files can be linked to directly. All text is escaped.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("-- src/main.rs --\nfn main() { println!(\"<hi>\") }\n");
+    /// let html = a.to_html();
+    ///
+    /// assert!(html.contains(r##"<a href="#src/main.rs">src/main.rs</a>"##));
+    /// assert!(html.contains("println!(&quot;&lt;hi&gt;&quot;)"));
+    /// ```
+    pub fn to_html(&self) -> String {
+        let mut s = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>txtar archive</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n"
+        );
+
+        let comment = self.comment_without_bom();
+        if !comment.is_empty() {
+            s.push_str(&format!(
+                "<pre class=\"comment\">{}</pre>\n",
+                escape(comment)
+            ));
+        }
+
+        let mut seen = HashSet::new();
+        let anchors: Vec<String> = self
+            .files
+            .iter()
+            .map(|f| unique_anchor(&f.name, &mut seen))
+            .collect();
+
+        s.push_str("<nav>\n<ul>\n");
+        for (f, anchor) in self.files.iter().zip(anchors.iter()) {
+            s.push_str(&format!(
+                "<li><a href=\"#{anchor}\">{}</a></li>\n",
+                escape(&f.name)
+            ));
+        }
+        s.push_str("</ul>\n</nav>\n");
+
+        for (f, anchor) in self.files.iter().zip(anchors.iter()) {
+            s.push_str(&format!(
+                "<section id=\"{anchor}\">\n<h2><a href=\"#{anchor}\">{}</a></h2>\n<pre><code>{}</code></pre>\n</section>\n",
+                escape(&f.name),
+                escape(&f.content)
+            ));
+        }
+        s.push_str("</body>\n</html>\n");
+
+        s
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn unique_anchor(name: &str, seen: &mut HashSet<String>) -> String {
+    let base: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '_' | '-' | '/' => c,
+            _ => '-',
+        })
+        .collect();
+    let base = if base.is_empty() {
+        "file".to_string()
+    } else {
+        base
+    };
+
+    let mut anchor = base.clone();
+    let mut n = 1;
+    while !seen.insert(anchor.clone()) {
+        n += 1;
+        anchor = format!("{base}-{n}");
+    }
+
+    anchor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchors_are_sanitized_and_unique() {
+        let a = Archive::from("<b>\n-- a b --\n-- a-b --\n-- a b --\n-- \"x\" --\n</pre>\n");
+        let html = a.to_html();
+
+        assert!(html.contains("<pre class=\"comment\">&lt;b&gt;\n</pre>"));
+        assert!(html.contains("<section id=\"a-b\">"));
+        assert!(html.contains("<section id=\"a-b-2\">"));
+        assert!(html.contains("<section id=\"a-b-3\">"));
+        assert!(html.contains("<a href=\"#-x-\">&quot;x&quot;</a>"));
+        assert!(html.contains("<pre><code>&lt;/pre&gt;\n</code></pre>"));
+    }
+}
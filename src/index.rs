@@ -0,0 +1,355 @@
+//! Storage for the files of an [Archive][crate::Archive] with a lazily built name index.
+use crate::{File, Source};
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::{Bound, Deref, Index, IndexMut, Range, RangeBounds},
+    slice::IterMut,
+    sync::OnceLock,
+    vec::Drain,
+};
+
+// Below this many files a linear scan is faster than building and probing a HashMap
+const INDEX_THRESHOLD: usize = 16;
+
+// The files of an archive in order, along with where each file was found in the input for
+// archives created by parsing and an index from each file name to the position of the first file
+// with that name.
+//
+// The sources are either empty or hold one entry per file, and every method that adds, removes or
+// reorders files keeps them aligned so that a source always describes the file at the same
+// position. Only [Files::iter_mut] and [IndexMut] hand out a `&mut File`, which can not move a
+// file.
+//
+// The index is built on the first lookup and kept up to date by [Files::push] and
+// [Files::content_mut], neither of which can change the name or position of an existing file. Any
+// other mutation (including through a `&mut File` that might be used to rename a file) discards
+// the index so that it can never go stale.
+#[derive(Default, Clone)]
+pub(crate) struct Files {
+    files: Vec<File>,
+    sources: Vec<Option<Source>>,
+    index: OnceLock<HashMap<String, usize>>,
+}
+
+impl Files {
+    // The position of the first file with the given name.
+    pub(crate) fn position(&self, name: &str) -> Option<usize> {
+        if self.files.len() < INDEX_THRESHOLD {
+            return self.files.iter().position(|f| f.name == name);
+        }
+
+        let index = self.index.get_or_init(|| {
+            let mut index = HashMap::with_capacity(self.files.len());
+            for (i, f) in self.files.iter().enumerate() {
+                index.entry(f.name.clone()).or_insert(i);
+            }
+            index
+        });
+
+        index.get(name).copied()
+    }
+
+    // Where the file at the given position was found in the input it was parsed from.
+    pub(crate) fn source(&self, ix: usize) -> Option<&Source> {
+        self.sources.get(ix)?.as_ref()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn sources(&self) -> &[Option<Source>] {
+        &self.sources
+    }
+
+    // Append a file, adding it to the index if one has been built.
+    pub(crate) fn push(&mut self, file: File) {
+        self.push_with_source(file, None);
+    }
+
+    // The content of the file at the given position, which can be modified without invalidating
+    // the index.
+    pub(crate) fn content_mut(&mut self, ix: usize) -> &mut String {
+        &mut self.files[ix].content
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> IterMut<'_, File> {
+        self.index.take();
+        self.files.iter_mut()
+    }
+
+    pub(crate) fn insert(&mut self, ix: usize, file: File) {
+        self.index.take();
+        if !self.sources.is_empty() {
+            self.sources.insert(ix, None);
+        }
+        self.files.insert(ix, file);
+    }
+
+    pub(crate) fn remove(&mut self, ix: usize) -> File {
+        self.index.take();
+        if !self.sources.is_empty() {
+            self.sources.remove(ix);
+        }
+        self.files.remove(ix)
+    }
+
+    pub(crate) fn swap(&mut self, a: usize, b: usize) {
+        self.index.take();
+        if !self.sources.is_empty() {
+            self.sources.swap(a, b);
+        }
+        self.files.swap(a, b);
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.index.take();
+        self.sources.truncate(len);
+        self.files.truncate(len);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<File> {
+        self.index.take();
+        self.sources.truncate(self.files.len().saturating_sub(1));
+        self.files.pop()
+    }
+
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(&File) -> bool) {
+        self.index.take();
+        let keep: Vec<bool> = self.files.iter().map(&mut f).collect();
+        if !self.sources.is_empty() {
+            let mut keep = keep.iter();
+            self.sources.retain(|_| *keep.next().unwrap());
+        }
+        let mut keep = keep.into_iter();
+        self.files.retain(|_| keep.next().unwrap());
+    }
+
+    pub(crate) fn drain(&mut self, range: impl RangeBounds<usize>) -> Drain<'_, File> {
+        self.index.take();
+        let range = to_range(range, self.files.len());
+        if !self.sources.is_empty() {
+            self.sources.drain(range.clone());
+        }
+        self.files.drain(range)
+    }
+
+    pub(crate) fn split_off(&mut self, at: usize) -> Files {
+        self.index.take();
+        let sources = match self.sources.is_empty() {
+            true => Vec::new(),
+            false => self.sources.split_off(at),
+        };
+
+        Self {
+            files: self.files.split_off(at),
+            sources,
+            index: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn partition(self, mut f: impl FnMut(&File) -> bool) -> (Files, Files) {
+        let (mut left, mut right) = (Files::default(), Files::default());
+        let mut sources = self.sources.into_iter();
+        for file in self.files {
+            let source = sources.next().flatten();
+            let side = if f(&file) { &mut left } else { &mut right };
+            side.push_with_source(file, source);
+        }
+
+        (left, right)
+    }
+
+    // Move all of the files from `other` to the end of these files.
+    pub(crate) fn append(&mut self, other: Files) {
+        let mut sources = other.sources.into_iter();
+        for file in other.files {
+            self.push_with_source(file, sources.next().flatten());
+        }
+    }
+
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.files.reserve(additional);
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        self.files.shrink_to_fit();
+        self.sources.shrink_to_fit();
+    }
+
+    fn push_with_source(&mut self, file: File, source: Option<Source>) {
+        if let Some(index) = self.index.get_mut() {
+            index.entry(file.name.clone()).or_insert(self.files.len());
+        }
+        if source.is_some() || !self.sources.is_empty() {
+            self.sources.resize(self.files.len(), None);
+            self.sources.push(source);
+        }
+        self.files.push(file);
+    }
+
+    #[cfg(test)]
+    fn is_indexed(&self) -> bool {
+        self.index.get().is_some()
+    }
+}
+
+fn to_range(range: impl RangeBounds<usize>, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i + 1,
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => len,
+    };
+
+    start..end
+}
+
+impl Deref for Files {
+    type Target = Vec<File>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.files
+    }
+}
+
+impl Index<usize> for Files {
+    type Output = File;
+
+    fn index(&self, ix: usize) -> &File {
+        &self.files[ix]
+    }
+}
+
+impl IndexMut<usize> for Files {
+    fn index_mut(&mut self, ix: usize) -> &mut File {
+        self.index.take();
+        &mut self.files[ix]
+    }
+}
+
+impl From<Vec<File>> for Files {
+    fn from(files: Vec<File>) -> Self {
+        Self {
+            files,
+            sources: Vec::new(),
+            index: OnceLock::new(),
+        }
+    }
+}
+
+impl FromIterator<File> for Files {
+    fn from_iter<T: IntoIterator<Item = File>>(iter: T) -> Self {
+        Self::from(Vec::from_iter(iter))
+    }
+}
+
+// Files created by parsing along with where they were found in the input.
+impl FromIterator<(File, Source)> for Files {
+    fn from_iter<T: IntoIterator<Item = (File, Source)>>(iter: T) -> Self {
+        let (files, sources): (Vec<_>, Vec<_>) =
+            iter.into_iter().map(|(f, s)| (f, Some(s))).unzip();
+
+        Self {
+            files,
+            sources,
+            index: OnceLock::new(),
+        }
+    }
+}
+
+impl Extend<File> for Files {
+    fn extend<T: IntoIterator<Item = File>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|file| self.push(file));
+    }
+}
+
+impl IntoIterator for Files {
+    type Item = File;
+    type IntoIter = std::vec::IntoIter<File>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.into_iter()
+    }
+}
+
+impl fmt::Debug for Files {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.files.fmt(f)
+    }
+}
+
+impl PartialEq for Files {
+    fn eq(&self, other: &Self) -> bool {
+        self.files == other.files
+    }
+}
+
+impl Eq for Files {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_is_invalidated_by_mutable_access() {
+        let mut files = Files::from(
+            (0..INDEX_THRESHOLD * 2)
+                .map(|i| File::new(format!("{}", i % INDEX_THRESHOLD), ""))
+                .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(files.position("3"), Some(3));
+        files[3].name = "renamed".to_string();
+        assert_eq!(files.position("3"), Some(INDEX_THRESHOLD + 3));
+        assert_eq!(files.position("renamed"), Some(3));
+        files.remove(0);
+        assert_eq!(files.position("renamed"), Some(2));
+        assert_eq!(files.position("missing"), None);
+    }
+
+    #[test]
+    fn index_is_kept_by_push_and_content_mut() {
+        let mut files: Files = (0..INDEX_THRESHOLD)
+            .map(|i| File::new(format!("{i}"), ""))
+            .collect();
+
+        assert_eq!(files.position("3"), Some(3));
+        files.content_mut(3).push_str("foo\n");
+        files.push(File::new("new", ""));
+        files.push(File::new("3", ""));
+
+        assert!(files.is_indexed());
+        assert_eq!(files.position("3"), Some(3));
+        assert_eq!(files.position("new"), Some(INDEX_THRESHOLD));
+        assert_eq!(files[3].content, "foo\n");
+    }
+
+    #[test]
+    fn sources_follow_their_files() {
+        let a = crate::Archive::from("-- a --\n-- b --\n-- c --\n-- d --\n");
+        let mut files = a.files;
+        let line = |files: &Files, name: &str| files.source(files.position(name)?).map(|s| s.line);
+
+        files.swap(0, 1);
+        files.insert(1, File::new("new", ""));
+        files.retain(|f| f.name != "c");
+        files.push(File::new("pushed", ""));
+        let (mut files, other) = files.partition(|f| f.name != "a");
+        files.append(other);
+
+        let names: Vec<_> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["b", "new", "d", "pushed", "a"]);
+        assert_eq!(line(&files, "a"), Some(1));
+        assert_eq!(line(&files, "b"), Some(2));
+        assert_eq!(line(&files, "d"), Some(4));
+        assert_eq!(line(&files, "new"), None);
+        assert_eq!(line(&files, "pushed"), None);
+        assert_eq!(files.remove(1).name, "new");
+        assert_eq!(files.split_off(2).source(0).map(|s| s.line), None);
+        assert_eq!(files.drain(..1).next().unwrap().name, "b");
+        assert_eq!(line(&files, "d"), Some(4));
+    }
+}
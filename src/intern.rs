@@ -0,0 +1,85 @@
+//! Interning of file names shared between many archives.
+use std::{collections::HashSet, sync::Arc};
+
+/// A set of interned file names that can be shared between archives.
+///
+/// Workloads that load many archives containing the same file names can intern those names so
+/// that each distinct name is only stored once, with every use sharing the same allocation. Two
+/// names returned from the same [Interner] are equal if and only if they point to the same
+/// allocation, so they can be compared using [Arc::ptr_eq] without looking at their contents.
+///
+/// An [Archive][crate::Archive] always owns the names of its files as a `String`, so interning
+/// only applies to a [SharedArchive][crate::SharedArchive] built using
+/// [SharedArchive::from_archive_interned][crate::SharedArchive::from_archive_interned].
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::{Archive, Interner, SharedArchive};
+/// use std::sync::Arc;
+///
+/// let mut names = Interner::new();
+/// let a = Archive::from("-- go.mod --\n-- main.go --\n");
+/// let b = Archive::from("-- go.mod --\n-- lib.go --\n");
+/// let a = SharedArchive::from_archive_interned(&a, &mut names);
+/// let b = SharedArchive::from_archive_interned(&b, &mut names);
+///
+/// assert!(Arc::ptr_eq(&a[0].name, &b[0].name));
+/// assert_eq!(&*b[1].name, "lib.go");
+/// assert_eq!(names.len(), 3);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Interner {
+    names: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    /// Create a new empty [Interner].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the interned copy of `name`, adding it to the interner if it is not already
+    /// present.
+    pub fn intern(&mut self, name: &str) -> Arc<str> {
+        if let Some(name) = self.names.get(name) {
+            return name.clone();
+        }
+
+        let name: Arc<str> = Arc::from(name);
+        self.names.insert(name.clone());
+
+        name
+    }
+
+    /// The number of distinct names that have been interned.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Whether or not any names have been interned.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Remove any names that are no longer referenced outside of the interner.
+    pub fn shrink(&mut self) {
+        self.names.retain(|name| Arc::strong_count(name) > 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shrink_drops_unused_names() {
+        let mut names = Interner::new();
+        let kept = names.intern("kept");
+        names.intern("dropped");
+
+        names.shrink();
+
+        assert_eq!(names.len(), 1);
+        assert!(Arc::ptr_eq(&kept, &names.intern("kept")));
+    }
+}
@@ -0,0 +1,63 @@
+//! Exchanging archives with other tools as JSON.
+use crate::Archive;
+
+impl Archive {
+    /// Render this archive as pretty printed JSON of the form
+    /// `{ "comment": "...", "files": [{ "name": "...", "content": "..." }] }`.
+    ///
+    /// Files are listed in the order they appear in the archive. See the [serde][crate::serde]
+    /// module for details of the representation.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("comment\n-- a.txt --\nfoo\n");
+    ///
+    /// assert_eq!(
+    ///     a.to_json(),
+    ///     r#"{
+    ///   "comment": "comment\n",
+    ///   "files": [
+    ///     {
+    ///       "name": "a.txt",
+    ///       "content": "foo\n"
+    ///     }
+    ///   ]
+    /// }"#
+    /// );
+    /// ```
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("serializing an archive is infallible")
+    }
+
+    /// Parse an archive from JSON in the format produced by [Archive::to_json].
+    ///
+    /// Both the `comment` and `files` fields are optional and default to being empty.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from_json(r#"{"files":[{"name":"a.txt","content":"foo\n"}]}"#).unwrap();
+    ///
+    /// assert_eq!(a.to_string(), "-- a.txt --\nfoo\n");
+    /// ```
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_round_trips() {
+        let a =
+            Archive::from("comment\n-- a --\n\"quoted\" \\ \u{1f600}\n-- b/c --\n-- a --\nagain");
+
+        assert_eq!(Archive::from_json(&a.to_json()).unwrap(), a);
+        assert!(Archive::from_json(r#"{"files":[{"name":"a"}]}"#).is_err());
+    }
+}
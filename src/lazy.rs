@@ -0,0 +1,74 @@
+//! Archives that are parsed the first time they are used.
+use crate::Archive;
+use std::{ops::Deref, sync::OnceLock};
+
+/// A `txtar` archive that is parsed from `'static` text the first time it is accessed, for use
+/// in a `static` alongside [include_str].
+///
+/// The parsed [Archive] is shared between all threads and is available either through
+/// [LazyArchive::get] or by dereferencing the [LazyArchive] directly.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::LazyArchive;
+///
+/// static EXAMPLE: LazyArchive = LazyArchive::new(include_str!("../testdata/example.txtar"));
+///
+/// assert_eq!(EXAMPLE["hello.txt"].content, "Hello, world!\n");
+/// assert_eq!(EXAMPLE.len(), 2);
+/// ```
+#[derive(Debug)]
+pub struct LazyArchive {
+    source: &'static str,
+    archive: OnceLock<Archive>,
+}
+
+impl LazyArchive {
+    /// Construct a new [LazyArchive] that will parse the given text when it is first accessed.
+    pub const fn new(source: &'static str) -> Self {
+        Self {
+            source,
+            archive: OnceLock::new(),
+        }
+    }
+
+    /// The text that the archive is parsed from.
+    pub const fn source(&self) -> &'static str {
+        self.source
+    }
+
+    /// The parsed [Archive], parsing it if this is the first time it has been accessed.
+    pub fn get(&self) -> &Archive {
+        self.archive.get_or_init(|| Archive::from(self.source))
+    }
+}
+
+impl Deref for LazyArchive {
+    type Target = Archive;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+impl AsRef<Archive> for LazyArchive {
+    fn as_ref(&self) -> &Archive {
+        self.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_is_only_parsed_once() {
+        static LAZY: LazyArchive = LazyArchive::new("-- a --\nfoo\n");
+
+        let first: *const Archive = LAZY.get();
+        let second: *const Archive = &*LAZY;
+
+        assert_eq!(first, second);
+        assert_eq!(LAZY["a"].content, "foo\n");
+    }
+}
@@ -78,12 +78,139 @@
     rustdoc::all,
     clippy::undocumented_unsafe_blocks
 )]
-use std::{fmt, fs, io, iter::IntoIterator, ops::Index, slice::Iter};
+#[cfg(feature = "flate2")]
+use gzip::read_to_string;
+use index::Files;
+use simple_txtar_scan as scan;
+use simple_txtar_scan::{missing_newline, BOM, MARKER, MARKER_END};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    io::{self, Write},
+    iter::IntoIterator,
+    ops::{Add, AddAssign, Index, IndexMut, Range, RangeBounds},
+    path::Path,
+    slice::{Iter, IterMut},
+    vec::Drain,
+};
 
-const NEWLINE_MARKER: &str = "\n-- ";
-const MARKER: &str = "-- ";
-const MARKER_END: &str = " --";
-const MARKER_LEN: usize = MARKER.len() + MARKER_END.len();
+#[cfg(not(feature = "flate2"))]
+use fs::read_to_string;
+
+mod binary;
+#[cfg(feature = "bincode")]
+mod bincode;
+mod borrowed;
+mod bytes;
+mod cache;
+#[cfg(feature = "camino")]
+mod camino;
+#[cfg(feature = "cap-std")]
+mod cap;
+pub mod codegen;
+#[cfg(feature = "compact_str")]
+mod compact;
+#[cfg(feature = "miette")]
+mod diagnostic;
+mod diff;
+mod embed;
+mod error;
+mod extract;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod filter;
+mod golden;
+#[cfg(feature = "flate2")]
+mod gzip;
+#[cfg(feature = "html")]
+mod html;
+mod index;
+#[cfg(feature = "intern")]
+mod intern;
+#[cfg(feature = "serde_json")]
+mod json;
+mod lazy;
+mod lint;
+mod markdown;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod pack;
+#[cfg(feature = "rayon")]
+mod par;
+mod parse;
+mod progress;
+mod pull;
+mod quote;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod shared;
+mod shell;
+mod sync;
+#[cfg(feature = "tar")]
+mod tar;
+#[cfg(feature = "tempfile")]
+mod temp;
+#[cfg(test)]
+mod test_dir;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "notify")]
+mod watch;
+mod writer;
+#[cfg(feature = "zip")]
+mod zip;
+
+pub use binary::Binary;
+pub use borrowed::{ArchiveRef, FileRef};
+pub use bytes::{parse_bytes_iter, ByteArchive, ByteFile, ParseBytesIter};
+#[cfg(feature = "compact_str")]
+pub use compact::{CompactArchive, CompactFile};
+#[cfg(feature = "miette")]
+pub use diagnostic::{SourceDiagnostic, Spanned};
+pub use embed::{StaticArchive, StaticDir, StaticFile};
+pub use error::{DuplicateName, Error, ParseError, ParseErrorKind, UnquoteError};
+pub use extract::{extract_from_reader, Conflict, ExtractOptions, ExtractReport, FileConflict};
+pub use golden::{DirComparison, FileDiff};
+#[cfg(feature = "intern")]
+pub use intern::Interner;
+pub use lazy::LazyArchive;
+pub use lint::{Lint, LintKind};
+pub use pack::{PackOptions, PackReport, SkipReason, Skipped};
+pub use parse::{parse_iter, Duplicates, Limits, ParseIter, ParseOptions};
+pub use progress::Progress;
+pub use pull::{parse_with, Event, Parser, Visitor};
+pub use quote::{needs_quote, quote, unquote};
+pub use shared::{DedupStats, SharedArchive, SharedFile};
+/// Parse a `txtar` file at compile time, expanding to a [StaticArchive] containing its comment
+/// and files.
+///
+/// The path is relative to the root of the calling crate (the directory containing its
+/// `Cargo.toml`) and the file is parsed in the same way as [Archive::from]. The crate is rebuilt
+/// whenever the file changes.
+///
+/// Missing, unreadable or invalid fixtures are compile errors rather than failures when the
+/// fixture is first used. The file is validated in the same way as by [codegen::generate], with
+/// the error listing the path and line number of every problem found.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::{include_txtar, StaticArchive};
+///
+/// static EXAMPLE: StaticArchive = include_txtar!("testdata/example.txtar");
+///
+/// assert_eq!(EXAMPLE.comment(), "An example archive used by the documentation.\n");
+/// assert_eq!(EXAMPLE["hello.txt"].content, "Hello, world!\n");
+/// ```
+#[cfg(feature = "macros")]
+pub use simple_txtar_macros::include_txtar;
+pub use sync::{DirDiff, SyncReport};
+#[cfg(feature = "tempfile")]
+pub use temp::TempDirHandle;
+#[cfg(feature = "wasm")]
+pub use wasm::JsArchive;
+#[cfg(feature = "notify")]
+pub use watch::DirWatcher;
+pub use writer::{ArchiveWriter, WriteOptions};
 
 /// Programatically build out a new [Archive] for later serialization as a txtar string.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -118,169 +245,1233 @@ impl Builder {
         self
     }
 
-    /// Set the top level comment for the resulting [Archive].
+    /// Append a [File] to the resulting [Archive].
+    ///
+    /// Anything that can be converted into a [File] is accepted, including `(name, content)`
+    /// tuples.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Builder, File};
+    ///
+    /// let mut builder = Builder::new();
+    /// builder
+    ///     .comment("a comment")
+    ///     .file(("example-1", "foo"))
+    ///     .file(File::new("example-2", "bar"));
+    ///
+    /// let archive = builder.build();
+    ///
+    /// assert_eq!(archive["example-1"].content, "foo");
+    /// ```
+    pub fn file(&mut self, file: impl Into<File>) -> &mut Self {
+        self.inner.add_file(file);
+        self
+    }
+
+    /// Consume the current state of this [Builder] to produce an [Archive], leaving the builder
+    /// empty so that it can be reused.
+    ///
+    /// As this only requires a mutable borrow, a builder can be constructed, populated and built
+    /// within a single expression.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Builder, File};
+    ///
+    /// let archive = Builder::new()
+    ///     .comment("a comment")
+    ///     .file(("example-1", "foo"))
+    ///     .file(File::new("example-2", "bar"))
+    ///     .build();
+    ///
+    /// let s = archive.to_string();
+    /// let expected = "\
+    /// a comment
+    /// -- example-1 --
+    /// foo
+    /// -- example-2 --
+    /// bar
+    /// ";
+    ///
+    /// assert_eq!(s, expected);
+    /// ```
+    pub fn build(&mut self) -> Archive {
+        std::mem::take(&mut self.inner)
+    }
+}
+
+/// An Archive is a collection of [File]s that have been read from a `txtar` file.
+///
+/// Archives can be created from a file on disk via the [Archive::from_file] method or directly
+/// from a `String` or `&str` using [Archive::from]. Once you have an Archive you can access the
+/// files by name using [Archive::get], index into the archive in the order that the contained
+/// [File]s were defined in the original `txtar` file, or iterate over the files in order.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Archive {
+    comment: String,
+    files: Files,
+}
+
+impl fmt::Display for Archive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_with_trailing_newline(f, self.comment_without_bom())?;
+        for file in self.files.iter() {
+            write!(f, "{file}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Archive {
+    /// Construct a new empty [Builder] for programatically creating an [Archive].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::builder()
+    ///     .comment("a comment\n")
+    ///     .file(("a.txt", "foo\n"))
+    ///     .build();
+    ///
+    /// assert_eq!(a, Archive::from("a comment\n-- a.txt --\nfoo\n"));
+    /// ```
+    pub fn builder() -> Builder {
+        Builder::new()
+    }
+
+    /// Construct an [Archive] with no comment containing the given files.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, File};
+    ///
+    /// let a = Archive::from_files(vec![File::new("a.txt", "foo\n"), File::new("b.txt", "bar\n")]);
+    ///
+    /// assert_eq!(a, Archive::from("-- a.txt --\nfoo\n-- b.txt --\nbar\n"));
+    /// ```
+    pub fn from_files(files: Vec<File>) -> Self {
+        Self {
+            comment: String::new(),
+            files: files.into(),
+        }
+    }
+
+    /// Parse a `txtar` archive from the file at the specified path.
+    ///
+    /// This will error if there are any issues with reading the file. To construct an [Archive]
+    /// directly from a `String` or `&str` that you already have in scope, use the `from` method.
+    ///
+    /// With the `flate2` feature enabled, files with a `.gz` extension or that begin with the
+    /// gzip magic bytes are decompressed before being parsed.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let res = Archive::from_file("my_txtar_archive");
+    /// ```
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let raw = read_to_string(path)?;
+
+        Ok(Self::from(raw))
+    }
+
+    /// Parse a `txtar` archive from the file at the specified path using the provided
+    /// [ParseOptions].
+    ///
+    /// This will error if there are any issues with reading the file or if the contents of the
+    /// file can not be parsed with the given options.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::{Archive, Error, ParseOptions};
+    ///
+    /// match Archive::from_file_with("my_txtar_archive", ParseOptions::new().strict(true)) {
+    ///     Ok(a) => println!("{} files", a.len()),
+    ///     Err(Error::Parse(e)) => eprintln!("invalid archive: {e}"),
+    ///     Err(e) => eprintln!("unable to read archive: {e}"),
+    /// }
+    /// ```
+    pub fn from_file_with(path: impl AsRef<Path>, opts: &ParseOptions) -> Result<Self, Error> {
+        let raw = read_to_string(path)?;
+
+        Ok(Self::from_str_with(&raw, opts)?)
+    }
+
+    /// Parse a `txtar` archive from the file at the specified path, replacing any invalid UTF-8
+    /// with [U+FFFD REPLACEMENT CHARACTER][char::REPLACEMENT_CHARACTER].
+    ///
+    /// See [Archive::from_bytes_lossy] for details of the returned line numbers. This will only
+    /// error if there are any issues with reading the file.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let (a, invalid) = Archive::from_file_lossy("my_txtar_archive").unwrap();
+    /// for line in invalid {
+    ///     eprintln!("invalid UTF-8 on line {line}");
+    /// }
+    /// ```
+    pub fn from_file_lossy(path: impl AsRef<Path>) -> io::Result<(Self, Vec<usize>)> {
+        let raw = fs::read(path)?;
+
+        Ok(Self::from_bytes_lossy(&raw))
+    }
+
+    /// Parse a `txtar` archive from raw bytes, replacing any invalid UTF-8 with
+    /// [U+FFFD REPLACEMENT CHARACTER][char::REPLACEMENT_CHARACTER].
+    ///
+    /// Along with the parsed archive, the 1-based line numbers of the input that contained
+    /// invalid UTF-8 are returned in ascending order so that callers can report where the
+    /// replacements occurred. The list is empty if the input was valid UTF-8.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let (a, invalid) = Archive::from_bytes_lossy(b"-- a --\nok\n-- b --\nbad \xff\n");
+    ///
+    /// assert_eq!(a["b"].content, "bad \u{fffd}\n");
+    /// assert_eq!(invalid, vec![4]);
+    /// ```
+    pub fn from_bytes_lossy(bytes: &[u8]) -> (Self, Vec<usize>) {
+        let mut s = String::with_capacity(bytes.len());
+        let mut invalid = Vec::new();
+        let mut line = 1;
+
+        for chunk in bytes.utf8_chunks() {
+            s.push_str(chunk.valid());
+            line += chunk.valid().matches('\n').count();
+            if !chunk.invalid().is_empty() {
+                s.push(char::REPLACEMENT_CHARACTER);
+                if invalid.last() != Some(&line) {
+                    invalid.push(line);
+                }
+            }
+        }
+
+        (Self::from(s), invalid)
+    }
+
+    /// Serialize this archive as a `txtar` file at the specified path, creating the file if it
+    /// does not exist and truncating it if it does.
+    ///
+    /// The output is identical to that of the [Display][fmt::Display] implementation, including
+    /// the insertion of any missing trailing newlines. Use [Archive::to_file_with] to replace the
+    /// file atomically.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("-- file1 --\nfoo");
+    /// a.to_file("my_txtar_archive").unwrap();
+    /// ```
+    pub fn to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut w = io::BufWriter::new(fs::File::create(path)?);
+        self.to_writer(&mut w)?;
+
+        w.flush()
+    }
+
+    /// Serialize this archive into the given writer.
+    ///
+    /// The comment and each file are written as they are reached rather than first building the
+    /// full `txtar` string in memory, with output identical to that of the
+    /// [Display][fmt::Display] implementation.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("comment\n-- file1 --\nfoo");
+    /// let mut buf = Vec::new();
+    /// a.to_writer(&mut buf).unwrap();
+    ///
+    /// assert_eq!(String::from_utf8(buf).unwrap(), a.to_string());
+    /// ```
+    pub fn to_writer(&self, mut w: impl Write) -> io::Result<()> {
+        write_with_trailing_newline(&mut w, self.comment_without_bom())?;
+        for file in self.files.iter() {
+            writeln!(w, "{MARKER}{}{MARKER_END}", file.name)?;
+            write_with_trailing_newline(&mut w, &file.content)?;
+        }
+
+        Ok(())
+    }
+
+    /// The canonical `txtar` representation of this archive.
+    ///
+    /// This is identical to the output of the [Display][fmt::Display] implementation: a single
+    /// space either side of each file name in the file markers and a trailing newline at the end
+    /// of the comment and the content of each file. Use [is_canonical] to check whether an
+    /// existing `txtar` string is already in this form.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{is_canonical, Archive};
+    ///
+    /// let a = Archive::from("comment\n--   a   --\nfoo");
+    /// let s = a.canonical_string();
+    ///
+    /// assert_eq!(s, "comment\n-- a --\nfoo\n");
+    /// assert!(is_canonical(&s));
+    /// ```
+    ///
+    /// The output buffer is allocated up front using [Archive::serialized_len], making this
+    /// preferable to `to_string` for large archives.
+    pub fn canonical_string(&self) -> String {
+        let mut s = String::with_capacity(self.serialized_len());
+        fmt::Write::write_fmt(&mut s, format_args!("{self}"))
+            .expect("writing to a String is infallible");
+
+        s
+    }
+
+    /// The length in bytes of the canonical `txtar` representation of this archive, as produced
+    /// by [Archive::canonical_string], [Archive::to_writer] and the [Display][fmt::Display]
+    /// implementation.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("comment\n--   a   --\nfoo");
+    ///
+    /// assert_eq!(a.serialized_len(), a.to_string().len());
+    /// ```
+    pub fn serialized_len(&self) -> usize {
+        self.files.iter().fold(
+            len_with_trailing_newline(self.comment_without_bom()),
+            |len, file| {
+                len + MARKER.len()
+                    + file.name.len()
+                    + MARKER_END.len()
+                    + 1
+                    + len_with_trailing_newline(&file.content)
+            },
+        )
+    }
+
+    /// Serialize this archive without making any changes to the comment or file content, using
+    /// the original file marker lines for any files parsed with [ParseOptions::lossless] enabled.
+    ///
+    /// Unlike the [Display][fmt::Display] implementation, trailing newlines are only inserted
+    /// where required to keep each file marker on its own line, and the newline added to the
+    /// content of the final file during parsing is omitted if it was not present in the input
+    /// (see [Archive::had_trailing_newline]). For an archive parsed in lossless mode this means that
+    /// the output is byte-for-byte identical to the original input.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, ParseOptions};
+    ///
+    /// let s = "comment\n--   padded   --\nfoo\n-- noNL --\nbar";
+    /// let a = Archive::from_str_with(s, ParseOptions::new().lossless(true)).unwrap();
+    ///
+    /// assert_eq!(a.to_string_exact(), s);
+    /// assert_eq!(a.to_string(), "comment\n-- padded --\nfoo\n-- noNL --\nbar\n");
+    /// ```
+    pub fn to_string_exact(&self) -> String {
+        Exact(self).to_string()
+    }
+
+    /// Serialize this archive into the given writer using the same output as
+    /// [Archive::to_string_exact].
+    pub fn to_writer_exact(&self, mut w: impl Write) -> io::Result<()> {
+        write!(w, "{}", Exact(self))
+    }
+
+    /// The optional comment at the top of the `txtar` archive.
+    ///
+    /// If no comment was provided this will return an empty string.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("comment line\n-- file1 --\nfoo");
+    /// assert_eq!(a.comment(), "comment line\n");
+    /// ```
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    // A leading byte order mark is never included when serializing.
+    fn comment_without_bom(&self) -> &str {
+        self.comment.strip_prefix(BOM).unwrap_or(&self.comment)
+    }
+
+    /// Attempt to get a file by name from the archive.
+    ///
+    /// Lookups by name are backed by an index that is built the first time it is needed and
+    /// discarded whenever the archive is modified in a way that could rename or reorder its files,
+    /// so repeated lookups into a large archive are O(1). Adding files to the end of the archive
+    /// and modifying file content using [Archive::content_mut] or [Archive::upsert] keep the index.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, File};
+    ///
+    /// let a = Archive::from("-- file1.txt --\nfoo");
+    /// assert_eq!(
+    ///     a.get("file1.txt"),
+    ///     Some(&File::new("file1.txt", "foo\n"))
+    /// );
+    ///
+    /// assert!(a.get("bar").is_none());
+    ///
+    /// // Alternatively you may also index directly into the Archive using the file name. This
+    /// // will panic if the file is not present in the archive.
+    /// assert_eq!(&a["file1.txt"], a.get("file1.txt").unwrap());
+    ///
+    /// // a["bar"]; <-- would panic
+    /// ```
+    pub fn get(&self, filename: &str) -> Option<&File> {
+        self.files.position(filename).map(|ix| &self.files[ix])
+    }
+
+    /// Iterate over the [File]s contained in this archive in the order they were specified in the
+    /// original `txtar` file.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, File};
+    ///
+    /// let a = Archive::from("-- file1.txt --\nfoo\n-- file2.txt --\nbar");
+    /// let mut it = a.iter();
+    /// assert_eq!(
+    ///     it.next(),
+    ///     Some(&File::new("file1.txt", "foo\n"))
+    /// );
+    ///
+    /// assert_eq!(
+    ///     it.next(),
+    ///     Some(&File::new("file2.txt", "bar\n"))
+    /// );
+    ///
+    /// assert_eq!(it.next(), None);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, File> {
+        self.files.iter()
+    }
+
+    /// The 1-based line number of the file marker for the file at `index` within the `txtar`
+    /// input this archive was parsed from.
+    ///
+    /// Positions follow each file as files are inserted, removed or reordered. Returns `None` if
+    /// `index` is out of bounds or the file was not created by parsing. The comment of an archive
+    /// always begins on line 1.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("comment\n-- a --\nfoo\nbar\n-- b --\nbaz");
+    /// a.add_file(("c", ""));
+    ///
+    /// assert_eq!(a.marker_line(0), Some(2));
+    /// assert_eq!(a.marker_line(1), Some(5));
+    /// assert_eq!(a.marker_line(2), None);
+    /// ```
+    pub fn marker_line(&self, index: usize) -> Option<usize> {
+        self.files.source(index).map(|s| s.line)
+    }
+
+    /// The 1-based line number of the first line of content for the file at `index` within the
+    /// `txtar` input this archive was parsed from.
+    ///
+    /// This is the line immediately following the file marker and can be combined with a line
+    /// offset within the content to report a location within the archive. Returns `None` in the
+    /// same cases as [Archive::marker_line].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("comment\n-- a --\nfoo\nbar\n-- b --\nbaz");
+    ///
+    /// assert_eq!(a.content_start_line(0), Some(3));
+    /// assert_eq!(a.content_start_line(1), Some(6));
+    /// ```
+    pub fn content_start_line(&self, index: usize) -> Option<usize> {
+        self.files.source(index).map(|s| s.line + 1)
+    }
+
+    /// The byte range of the file marker line for the file at `index` (including its line ending)
+    /// within the `txtar` input this archive was parsed from.
+    ///
+    /// Offsets are relative to the string passed to the parser, including any byte order mark
+    /// that was stripped. If [ParseOptions::normalize_newlines] was enabled then offsets are
+    /// relative to the normalized input. Returns `None` in the same cases as
+    /// [Archive::marker_line].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let s = "comment\n-- a --\nfoo\n-- b --\nbar";
+    /// let a = Archive::from(s);
+    ///
+    /// assert_eq!(a.marker_span(0), Some(8..16));
+    /// assert_eq!(&s[a.marker_span(1).unwrap()], "-- b --\n");
+    /// ```
+    pub fn marker_span(&self, index: usize) -> Option<Range<usize>> {
+        self.files.source(index).map(|s| s.marker.clone())
+    }
+
+    /// The byte range of the content of the file at `index` within the `txtar` input this archive
+    /// was parsed from.
+    ///
+    /// The range covers the content as it appeared in the input, so it will not include the
+    /// trailing newline added to the final file in an archive if it was missing (see
+    /// [Archive::had_trailing_newline]). Offsets are relative to the input in the same way as for
+    /// [Archive::marker_span]. Returns `None` in the same cases as [Archive::marker_line].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let s = "comment\n-- a --\nfoo\n-- b --\nbar";
+    /// let a = Archive::from(s);
+    ///
+    /// assert_eq!(&s[a.content_span(0).unwrap()], "foo\n");
+    /// assert_eq!(&s[a.content_span(1).unwrap()], "bar");
+    /// ```
+    pub fn content_span(&self, index: usize) -> Option<Range<usize>> {
+        self.files.source(index).map(|s| s.content.clone())
+    }
+
+    /// Whether the content of the file at `index` ended with a newline in the `txtar` input this
+    /// archive was parsed from.
+    ///
+    /// The `txtar` format specifies that a missing newline at the end of the input should be
+    /// treated as if it were present, so the final file in a parsed archive always has a trailing
+    /// newline added to its content. This records whether that was the case so that
+    /// [Archive::to_string_exact] can omit the newline again. Returns `true` if `index` is out of
+    /// bounds or the file was not created by parsing.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("-- a --\nfoo\n-- b --\nbar");
+    ///
+    /// assert!(a.had_trailing_newline(0));
+    /// assert!(!a.had_trailing_newline(1));
+    /// assert_eq!(a["b"].content, "bar\n");
+    /// assert_eq!(a.to_string_exact(), "-- a --\nfoo\n-- b --\nbar");
+    /// ```
+    pub fn had_trailing_newline(&self, index: usize) -> bool {
+        self.files
+            .source(index)
+            .is_none_or(|s| s.had_trailing_newline)
+    }
+
+    /// Translate a 1-based line number within the content of the named file into the
+    /// corresponding 1-based line number within the `txtar` input the archive was parsed from.
+    ///
+    /// This is useful for reporting errors found in an embedded file against the location in the
+    /// archive itself. Returns `None` if there is no file with the given name, if the file was not
+    /// created by parsing or if `content_line` is not a line of the file's content.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("comment\n-- a.rs --\nfn main() {\n    oops\n}\n-- b --\n");
+    ///
+    /// assert_eq!(a.archive_line_for("a.rs", 2), Some(4));
+    /// assert_eq!(a.archive_line_for("a.rs", 4), None);
+    /// assert_eq!(a.archive_line_for("missing", 1), None);
+    /// ```
+    pub fn archive_line_for(&self, filename: &str, content_line: usize) -> Option<usize> {
+        let ix = self.files.position(filename)?;
+        let (file, start) = (&self.files[ix], self.content_start_line(ix)?);
+
+        if content_line == 0 || content_line > file.content.lines().count() {
+            return None;
+        }
+
+        Some(start + content_line - 1)
+    }
+
+    /// Check whether the archive contains a file with the given name.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("-- file1.txt --\nfoo");
+    ///
+    /// assert!(a.contains("file1.txt"));
+    /// assert!(!a.contains("bar"));
+    /// ```
+    pub fn contains(&self, filename: &str) -> bool {
+        self.get(filename).is_some()
+    }
+
+    /// Attempt to get a mutable reference to a file by name from the archive.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("-- file1.txt --\nfoo");
+    /// if let Some(f) = a.get_mut("file1.txt") {
+    ///     f.content = "bar\n".to_string();
+    /// }
+    ///
+    /// assert_eq!(a["file1.txt"].content, "bar\n");
+    /// assert!(a.get_mut("missing").is_none());
+    /// ```
+    pub fn get_mut(&mut self, filename: &str) -> Option<&mut File> {
+        let ix = self.files.position(filename)?;
+        Some(&mut self.files[ix])
+    }
+
+    /// Attempt to get a mutable reference to the content of a file by name from the archive.
+    ///
+    /// Unlike [Archive::get_mut] this can not be used to rename the file, so the index backing
+    /// lookups by name is kept. Prefer this when repeatedly modifying the content of files in a
+    /// large archive.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("-- file1.txt --\nfoo\n");
+    /// if let Some(content) = a.content_mut("file1.txt") {
+    ///     content.push_str("bar\n");
+    /// }
+    ///
+    /// assert_eq!(a["file1.txt"].content, "foo\nbar\n");
+    /// assert!(a.content_mut("missing").is_none());
+    /// ```
+    pub fn content_mut(&mut self, filename: &str) -> Option<&mut String> {
+        let ix = self.files.position(filename)?;
+        Some(self.files.content_mut(ix))
+    }
+
+    /// Iterate mutably over the [File]s contained in this archive in the order they were
+    /// specified in the original `txtar` file.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("-- file1.txt --\n$TMP/foo\n-- file2.txt --\n$TMP/bar");
+    /// for f in a.iter_mut() {
+    ///     f.content = f.content.replace("$TMP", "/tmp");
+    /// }
+    ///
+    /// assert_eq!(a["file1.txt"].content, "/tmp/foo\n");
+    /// assert_eq!(a["file2.txt"].content, "/tmp/bar\n");
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, File> {
+        self.files.iter_mut()
+    }
+
+    /// Replace the comment at the top of the archive.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("old comment\n-- file1 --\nfoo");
+    /// a.set_comment("new comment\n");
+    /// assert_eq!(a.comment(), "new comment\n");
+    /// ```
+    pub fn set_comment(&mut self, comment: impl Into<String>) {
+        self.comment = comment.into();
+    }
+
+    /// Get a mutable reference to the comment at the top of the archive.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("generated\n-- file1 --\nfoo");
+    /// a.comment_mut().insert_str(0, "# ");
+    ///
+    /// assert_eq!(a.comment(), "# generated\n");
+    /// ```
+    pub fn comment_mut(&mut self) -> &mut String {
+        &mut self.comment
+    }
+
+    /// Append a single line to the comment at the top of the archive.
+    ///
+    /// A newline is added after `line`, along with one before it if the existing comment does not
+    /// already end with one.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("-- file1 --\nfoo");
+    /// a.push_comment_line("generated by:");
+    /// a.push_comment_line("  cargo run --bin regen");
+    ///
+    /// assert_eq!(a.comment(), "generated by:\n  cargo run --bin regen\n");
+    /// ```
+    pub fn push_comment_line(&mut self, line: &str) {
+        if !(self.comment.is_empty() || self.comment.ends_with('\n')) {
+            self.comment.push('\n');
+        }
+        self.comment.push_str(line);
+        self.comment.push('\n');
+    }
+
+    /// Replace the comment at the top of the archive, returning the updated archive.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("-- file1 --\nfoo").with_comment("a comment\n");
+    ///
+    /// assert_eq!(a.to_string(), "a comment\n-- file1 --\nfoo\n");
+    /// ```
+    pub fn with_comment(mut self, comment: impl Into<String>) -> Self {
+        self.set_comment(comment);
+        self
+    }
+
+    /// Append a [File] to the end of the archive.
+    ///
+    /// No check is made for an existing file with the same name: as with parsing, [Archive::get]
+    /// will return the first matching file.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, File};
+    ///
+    /// let mut a = Archive::from("-- file1 --\nfoo");
+    /// a.add_file(("file2", "bar\n"));
+    /// a.add_file(File::new("file3", "baz\n"));
+    ///
+    /// assert_eq!(a.to_string(), "-- file1 --\nfoo\n-- file2 --\nbar\n-- file3 --\nbaz\n");
+    /// ```
+    pub fn add_file(&mut self, file: impl Into<File>) {
+        self.files.push(file.into());
+    }
+
+    /// Append a [File] to the end of the archive, returning an error if the archive already
+    /// contains a file with the same name.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("-- file1 --\nfoo");
+    ///
+    /// assert!(a.try_add_file(("file2", "bar\n")).is_ok());
+    ///
+    /// let err = a.try_add_file(("file1", "baz\n")).unwrap_err();
+    /// assert_eq!(err.file.content, "baz\n");
+    /// assert_eq!(a.len(), 2);
+    /// ```
+    pub fn try_add_file(&mut self, file: impl Into<File>) -> Result<(), DuplicateName> {
+        let file = file.into();
+        if self.contains(&file.name) {
+            return Err(DuplicateName { file });
+        }
+        self.files.push(file);
+
+        Ok(())
+    }
+
+    /// Move all of the files from `other` onto the end of this archive.
+    ///
+    /// The comment from `other` is appended to the comment of this archive, with a newline
+    /// inserted between the two if needed. No attempt is made to merge files with the same name:
+    /// as with parsing, [Archive::get] will return the first matching file.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("base fixtures\n-- a.txt --\nfoo");
+    /// a.append_archive(Archive::from("overlay\n-- b.txt --\nbar"));
+    ///
+    /// assert_eq!(a.comment(), "base fixtures\noverlay\n");
+    /// assert_eq!(a.len(), 2);
+    /// ```
+    pub fn append_archive(&mut self, other: Archive) {
+        if !other.comment.is_empty() {
+            if !(self.comment.is_empty() || self.comment.ends_with('\n')) {
+                self.comment.push('\n');
+            }
+            self.comment.push_str(&other.comment);
+        }
+        self.files.append(other.files);
+    }
+
+    /// Set the content of the first file with the given name, appending a new file to the end of
+    /// the archive if there is no such file.
+    ///
+    /// Returns the previous content of the file if it was already present.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("-- config.toml --\nold = true");
+    ///
+    /// assert_eq!(a.upsert("config.toml", "new = true\n"), Some("old = true\n".to_string()));
+    /// assert_eq!(a.upsert("other.toml", "x = 1\n"), None);
+    /// assert_eq!(a.to_string(), "-- config.toml --\nnew = true\n-- other.toml --\nx = 1\n");
+    /// ```
+    pub fn upsert(&mut self, filename: &str, content: impl Into<String>) -> Option<String> {
+        let content = content.into();
+        match self.content_mut(filename) {
+            Some(existing) => Some(std::mem::replace(existing, content)),
+            None => {
+                self.files.push(File::new(filename, content));
+                None
+            }
+        }
+    }
+
+    /// Get a mutable reference to the first file with the given name, appending a new file with
+    /// content generated by `f` to the end of the archive if there is no such file.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("-- config.toml --\nold = true");
+    ///
+    /// a.get_or_insert_with("config.toml", || "ignored".to_string());
+    /// a.get_or_insert_with("other.toml", || "x = 1\n".to_string())
+    ///     .content
+    ///     .push_str("y = 2\n");
+    ///
+    /// assert_eq!(a["config.toml"].content, "old = true\n");
+    /// assert_eq!(a["other.toml"].content, "x = 1\ny = 2\n");
+    /// ```
+    pub fn get_or_insert_with(&mut self, filename: &str, f: impl FnOnce() -> String) -> &mut File {
+        let ix = match self.files.position(filename) {
+            Some(ix) => ix,
+            None => {
+                self.files.push(File::new(filename, f()));
+                self.files.len() - 1
+            }
+        };
+
+        &mut self.files[ix]
+    }
+
+    /// Remove the first file with the given name from the archive, returning it if it was present.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, File};
+    ///
+    /// let mut a = Archive::from("-- file1 --\nfoo\n-- file2 --\nbar");
+    ///
+    /// assert_eq!(a.remove("file1"), Some(File::new("file1", "foo\n")));
+    /// assert_eq!(a.remove("file1"), None);
+    /// assert_eq!(a.to_string(), "-- file2 --\nbar\n");
+    /// ```
+    pub fn remove(&mut self, filename: &str) -> Option<File> {
+        let ix = self.files.position(filename)?;
+
+        Some(self.files.remove(ix))
+    }
+
+    /// Create a new empty archive with space for at least `capacity` files without reallocating.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::with_capacity(10);
+    /// assert!(a.capacity() >= 10);
+    ///
+    /// (0..10).for_each(|i| a.add_file((format!("{i}.txt"), "")));
+    /// assert_eq!(a.len(), 10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_files(Vec::with_capacity(capacity))
+    }
+
+    /// The number of files this archive can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.files.capacity()
+    }
+
+    /// Reserve space for at least `additional` more files to be added to this archive without
+    /// reallocating.
+    pub fn reserve(&mut self, additional: usize) {
+        self.files.reserve(additional);
+    }
+
+    /// Release any excess capacity held by this archive, including the spare capacity of the
+    /// comment and of the name and content of each file.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::with_capacity(10);
+    /// a.add_file(("a.txt", String::with_capacity(1024)));
+    /// a.shrink_to_fit();
+    ///
+    /// assert_eq!(a.capacity(), 1);
+    /// assert_eq!(a[0].content.capacity(), 0);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.comment.shrink_to_fit();
+        self.files.shrink_to_fit();
+        for file in self.files.iter_mut() {
+            file.name.shrink_to_fit();
+            file.content.shrink_to_fit();
+        }
+    }
+
+    /// The number of files contained in this archive.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("comment\n-- file1 --\nfoo\n-- file2 --\nbar");
+    /// assert_eq!(a.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether or not this archive contains any files.
+    ///
+    /// An archive containing only a comment is considered to be empty.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// assert!(Archive::from("just a comment").is_empty());
+    /// assert!(!Archive::from("-- file1 --\nfoo").is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Insert a [File] at the given position within the archive, shifting all files after it
+    /// along by one.
+    ///
+    /// # Panics
+    /// Panics if `index > len`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("-- file1 --\nfoo\n-- file3 --\nbaz");
+    /// a.insert_at(1, ("file2", "bar\n"));
+    ///
+    /// assert_eq!(a[1].name, "file2");
+    /// assert_eq!(a[2].name, "file3");
+    /// ```
+    pub fn insert_at(&mut self, index: usize, file: impl Into<File>) {
+        self.files.insert(index, file.into());
+    }
+
+    /// Swap the positions of two files within the archive.
+    ///
+    /// # Panics
+    /// Panics if either `a` or `b` are out of bounds.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("-- file1 --\nfoo\n-- file2 --\nbar");
+    /// a.swap(0, 1);
+    ///
+    /// assert_eq!(a.to_string(), "-- file2 --\nbar\n-- file1 --\nfoo\n");
+    /// ```
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.files.swap(a, b);
+    }
+
+    /// Shorten the archive, keeping the first `len` files and dropping the rest.
+    ///
+    /// This has no effect if `len` is greater than the current number of files.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("-- file1 --\nfoo\n-- file2 --\nbar\n-- file3 --\nbaz");
+    /// a.truncate(1);
+    ///
+    /// assert_eq!(a.to_string(), "-- file1 --\nfoo\n");
+    /// ```
+    pub fn truncate(&mut self, len: usize) {
+        self.files.truncate(len);
+    }
+
+    /// Remove the last file from the archive and return it, or `None` if the archive is empty.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, File};
+    ///
+    /// let mut a = Archive::from("-- file1 --\nfoo\n-- file2 --\nbar");
+    ///
+    /// assert_eq!(a.pop(), Some(File::new("file2", "bar\n")));
+    /// assert_eq!(a.pop(), Some(File::new("file1", "foo\n")));
+    /// assert_eq!(a.pop(), None);
+    /// ```
+    pub fn pop(&mut self) -> Option<File> {
+        self.files.pop()
+    }
+
+    /// Retain only the files for which the given predicate returns `true`, preserving their
+    /// relative order.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("-- a.json --\n{}\n-- b.txt --\nfoo\n-- c.json --\n[]");
+    /// a.retain(|f| f.name.ends_with(".json"));
+    ///
+    /// assert_eq!(a.to_string(), "-- a.json --\n{}\n-- c.json --\n[]\n");
+    /// ```
+    pub fn retain(&mut self, f: impl FnMut(&File) -> bool) {
+        self.files.retain(f);
+    }
+
+    /// Remove the specified range of files from the archive, returning them as an iterator.
+    ///
+    /// Use `drain(..)` to move every file out of the archive while leaving the comment intact.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds or if its start is greater than its end.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("comment\n-- file1 --\nfoo\n-- file2 --\nbar");
+    /// let names: Vec<String> = a.drain(..).map(|f| f.name).collect();
+    ///
+    /// assert_eq!(names, vec!["file1", "file2"]);
+    /// assert!(a.is_empty());
+    /// assert_eq!(a.comment(), "comment\n");
+    /// ```
+    pub fn drain(&mut self, range: impl RangeBounds<usize>) -> Drain<'_, File> {
+        self.files.drain(range)
+    }
+
+    /// Replace the prefix `from` with `to` in the names of all files that begin with `from`,
+    /// preserving their positions within the archive.
+    ///
+    /// As with [Archive::sub_archive] the prefix is matched as a plain string. Returns the
+    /// number of files that were renamed.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from("-- old/a --\n1\n-- other/b --\n2\n-- old/c/d --\n3");
+    ///
+    /// assert_eq!(a.rename_prefix("old/", "new/"), 2);
+    /// assert_eq!(a.to_string(), "-- new/a --\n1\n-- other/b --\n2\n-- new/c/d --\n3\n");
+    /// ```
+    pub fn rename_prefix(&mut self, from: &str, to: &str) -> usize {
+        let mut n = 0;
+        for f in self.files.iter_mut() {
+            if let Some(rest) = f.name.strip_prefix(from) {
+                f.name = format!("{to}{rest}");
+                n += 1;
+            }
+        }
+
+        n
+    }
+
+    /// Apply a function to the name of every file in the archive, returning the updated archive.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("-- a --\n1\n-- b --\n2").map_names(|name| format!("data/{name}"));
+    ///
+    /// assert_eq!(a.to_string(), "-- data/a --\n1\n-- data/b --\n2\n");
+    /// ```
+    pub fn map_names(mut self, mut f: impl FnMut(&str) -> String) -> Self {
+        for file in self.files.iter_mut() {
+            file.name = f(&file.name);
+        }
+
+        self
+    }
+
+    /// Apply a function to the content of every file in the archive, returning the updated
+    /// archive.
     ///
     /// ## Example
     /// ```rust
-    /// use simple_txtar::{Builder, File};
-    ///
-    /// let mut builder = Builder::new();
-    /// builder
-    ///     .comment("a comment")
-    ///     .file(("example-1", "foo"))
-    ///     .file(File::new("example-2", "bar"));
+    /// use simple_txtar::Archive;
     ///
-    /// let archive = builder.build();
+    /// let a = Archive::from("-- a --\n$TMP/1\n-- b --\n$TMP/2")
+    ///     .map_contents(|content| content.replace("$TMP", "/tmp"));
     ///
-    /// assert_eq!(archive["example-1"].content, "foo");
+    /// assert_eq!(a.to_string(), "-- a --\n/tmp/1\n-- b --\n/tmp/2\n");
     /// ```
-    pub fn file(&mut self, file: impl Into<File>) -> &mut Self {
-        self.inner.files.push(file.into());
+    pub fn map_contents(mut self, mut f: impl FnMut(&str) -> String) -> Self {
+        for file in self.files.iter_mut() {
+            file.content = f(&file.content);
+        }
+
         self
     }
 
-    /// Set the top level comment for the resulting [Archive].
+    /// Split the archive in two at the given index, returning a new archive containing the files
+    /// from `at` onwards and leaving this archive with the files before it.
     ///
-    /// ## Example
-    /// ```rust
-    /// use simple_txtar::{Builder, File};
+    /// The returned archive is given a copy of the comment from this archive.
     ///
-    /// let mut builder = Builder::new();
-    /// builder
-    ///     .comment("a comment")
-    ///     .file(("example-1", "foo"))
-    ///     .file(File::new("example-2", "bar"));
+    /// # Panics
+    /// Panics if `at > len`.
     ///
-    /// let archive = builder.build();
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
     ///
-    /// let s = archive.to_string();
-    /// let expected = "\
-    /// a comment
-    /// -- example-1 --
-    /// foo
-    /// -- example-2 --
-    /// bar
-    /// ";
+    /// let mut a = Archive::from("comment\n-- file1 --\nfoo\n-- file2 --\nbar");
+    /// let b = a.split_off(1);
     ///
-    /// assert_eq!(s, expected);
+    /// assert_eq!(a.to_string(), "comment\n-- file1 --\nfoo\n");
+    /// assert_eq!(b.to_string(), "comment\n-- file2 --\nbar\n");
     /// ```
-    pub fn build(self) -> Archive {
-        self.inner
-    }
-}
-
-/// An Archive is a collection of [File]s that have been read from a `txtar` file.
-///
-/// Archives can be created from a file on disk via the [Archive::from_file] method or directly
-/// from a `String` or `&str` using [Archive::from]. Once you have an Archive you can access the
-/// files by name using [Archive::get], index into the archive in the order that the contained
-/// [File]s were defined in the original `txtar` file, or iterate over the files in order.
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
-pub struct Archive {
-    comment: String,
-    files: Vec<File>,
-}
-
-impl fmt::Display for Archive {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", fix_trailing_newline(&self.comment))?;
-        for file in self.files.iter() {
-            write!(f, "{file}")?;
+    pub fn split_off(&mut self, at: usize) -> Archive {
+        Archive {
+            comment: self.comment.clone(),
+            files: self.files.split_off(at),
         }
-
-        Ok(())
     }
-}
 
-impl Archive {
-    /// Parse a `txtar` archive from the file at the specified path.
+    /// Consume this archive, splitting its files into two archives: the first containing the
+    /// files for which the predicate returns `true` and the second containing the rest.
     ///
-    /// This will error if there are any issues with reading the file. To construct an [Archive]
-    /// directly from a `String` or `&str` that you already have in scope, use the `from` method.
+    /// Both archives are given a copy of the comment from this archive and the relative order of
+    /// the files is preserved.
     ///
     /// ## Example
-    /// ```no_run
+    /// ```rust
     /// use simple_txtar::Archive;
     ///
-    /// let res = Archive::from_file("my_txtar_archive");
+    /// let a = Archive::from("-- in/a --\n1\n-- out/a --\n2\n-- in/b --\n3");
+    /// let (inputs, outputs) = a.partition(|f| f.name.starts_with("in/"));
+    ///
+    /// assert_eq!(inputs.to_string(), "-- in/a --\n1\n-- in/b --\n3\n");
+    /// assert_eq!(outputs.to_string(), "-- out/a --\n2\n");
     /// ```
-    pub fn from_file(path: &str) -> io::Result<Self> {
-        let raw = fs::read_to_string(path)?;
+    pub fn partition(self, f: impl FnMut(&File) -> bool) -> (Archive, Archive) {
+        let (left, right) = self.files.partition(f);
 
-        Ok(Self::from(raw.as_str()))
+        (
+            Archive {
+                comment: self.comment.clone(),
+                files: left,
+            },
+            Archive {
+                comment: self.comment,
+                files: right,
+            },
+        )
     }
 
-    /// The optional comment at the top of the `txtar` archive.
+    /// Create a new archive containing copies of the files whose names begin with `prefix`.
     ///
-    /// If no comment was provided this will return an empty string.
+    /// The prefix is matched as a plain string so it should normally end in a `/` in order to
+    /// select a directory. The new archive is given a copy of the comment from this archive. See
+    /// [Archive::sub_archive_stripped] for removing the prefix from the selected file names.
     ///
     /// ## Example
     /// ```rust
     /// use simple_txtar::Archive;
     ///
-    /// let a = Archive::from("comment line\n-- file1 --\nfoo");
-    /// assert_eq!(a.comment(), "comment line\n");
+    /// let a = Archive::from("-- testdata/a --\n1\n-- other/b --\n2\n-- testdata/c/d --\n3");
+    /// let sub = a.sub_archive("testdata/");
+    ///
+    /// assert_eq!(sub.to_string(), "-- testdata/a --\n1\n-- testdata/c/d --\n3\n");
     /// ```
-    pub fn comment(&self) -> &str {
-        &self.comment
+    pub fn sub_archive(&self, prefix: &str) -> Archive {
+        Archive {
+            comment: self.comment.clone(),
+            files: self
+                .files
+                .iter()
+                .filter(|f| f.name.starts_with(prefix))
+                .cloned()
+                .collect(),
+        }
     }
 
-    /// Attempt to get a file by name from the archive.
+    /// Create a new archive containing copies of the files whose names begin with `prefix`, with
+    /// the prefix removed from their names.
+    ///
+    /// Files named exactly `prefix` are not included as they would be left with an empty name.
     ///
     /// ## Example
     /// ```rust
-    /// use simple_txtar::{Archive, File};
-    ///
-    /// let a = Archive::from("-- file1.txt --\nfoo");
-    /// assert_eq!(
-    ///     a.get("file1.txt"),
-    ///     Some(&File {
-    ///         name: "file1.txt".to_string(),
-    ///         content: "foo\n".to_string()
-    ///     })
-    /// );
-    ///
-    /// assert!(a.get("bar").is_none());
+    /// use simple_txtar::Archive;
     ///
-    /// // Alternatively you may also index directly into the Archive using the file name. This
-    /// // will panic if the file is not present in the archive.
-    /// assert_eq!(&a["file1.txt"], a.get("file1.txt").unwrap());
+    /// let a = Archive::from("-- testdata/a --\n1\n-- other/b --\n2\n-- testdata/c/d --\n3");
+    /// let sub = a.sub_archive_stripped("testdata/");
     ///
-    /// // a["bar"]; <-- would panic
+    /// assert_eq!(sub.to_string(), "-- a --\n1\n-- c/d --\n3\n");
     /// ```
-    pub fn get(&self, filename: &str) -> Option<&File> {
-        self.files.iter().find(|f| f.name == filename)
+    pub fn sub_archive_stripped(&self, prefix: &str) -> Archive {
+        Archive {
+            comment: self.comment.clone(),
+            files: self
+                .files
+                .iter()
+                .filter_map(|f| match f.name.strip_prefix(prefix) {
+                    Some(name) if !name.is_empty() => Some(File::new(name, f.content.clone())),
+                    _ => None,
+                })
+                .collect(),
+        }
     }
 
-    /// Iterate over the [File]s contained in this archive in the order they were specified in the
-    /// original `txtar` file.
+    /// Rename the first file with the name `from` to have the name `to`, preserving its position
+    /// within the archive.
+    ///
+    /// Returns `false` if there was no file named `from` in the archive.
     ///
     /// ## Example
     /// ```rust
-    /// use simple_txtar::{Archive, File};
-    ///
-    /// let a = Archive::from("-- file1.txt --\nfoo\n-- file2.txt --\nbar");
-    /// let mut it = a.iter();
-    /// assert_eq!(
-    ///     it.next(),
-    ///     Some(&File {
-    ///         name: "file1.txt".to_string(),
-    ///         content: "foo\n".to_string()
-    ///     })
-    /// );
+    /// use simple_txtar::Archive;
     ///
-    /// assert_eq!(
-    ///     it.next(),
-    ///     Some(&File {
-    ///         name: "file2.txt".to_string(),
-    ///         content: "bar\n".to_string()
-    ///     })
-    /// );
+    /// let mut a = Archive::from("-- file1 --\nfoo\n-- file2 --\nbar");
     ///
-    /// assert_eq!(it.next(), None);
+    /// assert!(a.rename("file1", "renamed"));
+    /// assert!(!a.rename("file1", "renamed"));
+    /// assert_eq!(a.to_string(), "-- renamed --\nfoo\n-- file2 --\nbar\n");
     /// ```
-    pub fn iter(&self) -> Iter<'_, File> {
-        self.files.iter()
+    pub fn rename(&mut self, from: &str, to: impl Into<String>) -> bool {
+        match self.files.position(from) {
+            Some(ix) => {
+                self.files[ix].name = to.into();
+                true
+            }
+            None => false,
+        }
     }
 }
 
@@ -300,6 +1491,43 @@ impl Index<&str> for Archive {
     }
 }
 
+impl IndexMut<usize> for Archive {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.files[index]
+    }
+}
+
+impl IndexMut<&str> for Archive {
+    fn index_mut(&mut self, index: &str) -> &mut Self::Output {
+        self.get_mut(index).expect("unknown file")
+    }
+}
+
+/// Concatenate two archives using [Archive::append_archive].
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::Archive;
+///
+/// let a = Archive::from("-- a.txt --\nfoo") + Archive::from("-- b.txt --\nbar");
+///
+/// assert_eq!(a.to_string(), "-- a.txt --\nfoo\n-- b.txt --\nbar\n");
+/// ```
+impl Add for Archive {
+    type Output = Archive;
+
+    fn add(mut self, rhs: Archive) -> Self::Output {
+        self.append_archive(rhs);
+        self
+    }
+}
+
+impl AddAssign for Archive {
+    fn add_assign(&mut self, rhs: Archive) {
+        self.append_archive(rhs);
+    }
+}
+
 impl IntoIterator for Archive {
     type Item = File;
     type IntoIter = std::vec::IntoIter<File>;
@@ -311,25 +1539,17 @@ impl IntoIterator for Archive {
 
 impl From<&str> for Archive {
     fn from(s: &str) -> Self {
-        let (comment, mut name_after) = find_file_marker(s);
-        let mut a = Archive {
-            comment,
-            files: Vec::new(),
-        };
-
-        let mut content;
-        while let Some((name, after)) = name_after {
-            (content, name_after) = find_file_marker(after);
-            a.files.push(File::new(name, content));
-        }
-
-        a
+        let body = scan::strip_bom(s);
+        parse::parse(body, s.len() - body.len(), &ParseOptions::default())
     }
 }
 
+/// Parsing an owned `String` reuses its buffer for the content of the final file (or the comment
+/// if there are no files) rather than copying it, provided that it makes up at least half of the
+/// input. This avoids copying the bulk of an archive that is dominated by a single large file.
 impl From<String> for Archive {
     fn from(s: String) -> Self {
-        Self::from(s.as_str())
+        parse::parse_owned(s, &ParseOptions::default())
     }
 }
 
@@ -339,6 +1559,141 @@ impl From<&String> for Archive {
     }
 }
 
+impl From<Vec<File>> for Archive {
+    fn from(files: Vec<File>) -> Self {
+        Self::from_files(files)
+    }
+}
+
+/// Converting to a map of file names to their content discards the comment and the order of the
+/// files. If multiple files share the same name then the content of the first one is kept, in the
+/// same way as looking up a file by name with [Archive::get].
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::Archive;
+/// use std::collections::BTreeMap;
+///
+/// let a = Archive::from("comment\n-- b.txt --\nfoo\n-- a.txt --\nbar\n-- b.txt --\nbaz\n");
+/// let m = BTreeMap::from(a);
+///
+/// assert_eq!(m.len(), 2);
+/// assert_eq!(m["a.txt"], "bar\n");
+/// assert_eq!(m["b.txt"], "foo\n");
+/// ```
+impl From<Archive> for BTreeMap<String, String> {
+    fn from(a: Archive) -> Self {
+        let mut m = BTreeMap::new();
+        for f in a {
+            m.entry(f.name).or_insert(f.content);
+        }
+
+        m
+    }
+}
+
+/// See the [BTreeMap] implementation for details of how the archive is converted.
+impl From<Archive> for HashMap<String, String> {
+    fn from(a: Archive) -> Self {
+        let mut m = HashMap::with_capacity(a.len());
+        for f in a {
+            m.entry(f.name).or_insert(f.content);
+        }
+
+        m
+    }
+}
+
+/// An [Archive] built from a map has no comment and contains its files sorted by name.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::Archive;
+/// use std::collections::BTreeMap;
+///
+/// let m = BTreeMap::from([("b.txt", "foo\n"), ("a.txt", "bar\n")]);
+///
+/// assert_eq!(Archive::from(m).to_string(), "-- a.txt --\nbar\n-- b.txt --\nfoo\n");
+/// ```
+impl<K, V> From<BTreeMap<K, V>> for Archive
+where
+    K: Into<String>,
+    V: Into<String>,
+{
+    fn from(m: BTreeMap<K, V>) -> Self {
+        m.into_iter().map(|(k, v)| File::new(k, v)).collect()
+    }
+}
+
+/// The files are sorted by name, so that the resulting [Archive] does not depend on the
+/// iteration order of the map.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::Archive;
+/// use std::collections::HashMap;
+///
+/// let m = HashMap::from([("b.txt", "foo\n"), ("a.txt", "bar\n")]);
+///
+/// assert_eq!(Archive::from(m).to_string(), "-- a.txt --\nbar\n-- b.txt --\nfoo\n");
+/// ```
+impl<K, V, S> From<HashMap<K, V, S>> for Archive
+where
+    K: Into<String>,
+    V: Into<String>,
+{
+    fn from(m: HashMap<K, V, S>) -> Self {
+        let mut files: Vec<File> = m.into_iter().map(|(k, v)| File::new(k, v)).collect();
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self::from_files(files)
+    }
+}
+
+/// An [Archive] with no comment can be collected from an iterator of anything that can be
+/// converted into a [File].
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::Archive;
+///
+/// let a: Archive = ["a", "b"]
+///     .into_iter()
+///     .map(|name| (format!("{name}.txt"), format!("{name}\n")))
+///     .collect();
+///
+/// assert_eq!(a.to_string(), "-- a.txt --\na\n-- b.txt --\nb\n");
+/// ```
+impl<F> FromIterator<F> for Archive
+where
+    F: Into<File>,
+{
+    fn from_iter<I: IntoIterator<Item = F>>(iter: I) -> Self {
+        Self::from_files(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Files are appended to the end of the [Archive] in the order they are yielded by the iterator.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::Archive;
+///
+/// let mut a = Archive::from("-- a.txt --\na");
+/// a.extend(Archive::from("-- b.txt --\nb"));
+/// a.extend([("c.txt", "c\n")]);
+///
+/// assert_eq!(a.to_string(), "-- a.txt --\na\n-- b.txt --\nb\n-- c.txt --\nc\n");
+/// ```
+impl<F> Extend<F> for Archive
+where
+    F: Into<File>,
+{
+    fn extend<I: IntoIterator<Item = F>>(&mut self, iter: I) {
+        self.files.extend(iter.into_iter().map(Into::into));
+    }
+}
+
 /// A File is a single file within an [Archive].
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct File {
@@ -348,6 +1703,21 @@ pub struct File {
     pub content: String,
 }
 
+// Where a parsed File was found in the input it was parsed from
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Source {
+    // The 1-based line number of the file marker
+    line: usize,
+    // Byte range of the file marker line, including its line ending
+    marker: Range<usize>,
+    // Byte range of the file content as it appeared in the input
+    content: Range<usize>,
+    // Whether the content ended with a newline in the input
+    had_trailing_newline: bool,
+    // The verbatim file marker line (including its line ending) when parsed in lossless mode
+    raw_marker: Option<String>,
+}
+
 impl File {
     /// Construct a new [File].
     pub fn new(name: impl Into<String>, content: impl Into<String>) -> Self {
@@ -356,12 +1726,84 @@ impl File {
             content: content.into(),
         }
     }
+
+    /// Append a single line to the content of this file.
+    ///
+    /// A newline is added after `line`, along with one before it if the existing content does not
+    /// already end with one.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::File;
+    ///
+    /// let mut f = File::new("stdout", "");
+    /// f.push_line("first");
+    /// f.push_line("second");
+    ///
+    /// assert_eq!(f.content, "first\nsecond\n");
+    /// ```
+    pub fn push_line(&mut self, line: &str) {
+        if !(self.content.is_empty() || self.content.ends_with('\n')) {
+            self.content.push('\n');
+        }
+        self.content.push_str(line);
+        self.content.push('\n');
+    }
+
+    /// Append a string to the content of this file as-is.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::File;
+    ///
+    /// let mut f = File::new("stdout", "partial");
+    /// f.append_str(" line\n");
+    ///
+    /// assert_eq!(f.content, "partial line\n");
+    /// ```
+    pub fn append_str(&mut self, s: &str) {
+        self.content.push_str(s);
+    }
+
+    /// Remove all content from this file, leaving its name unchanged.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::File;
+    ///
+    /// let mut f = File::new("stdout", "some output\n");
+    /// f.clear();
+    ///
+    /// assert_eq!(f, File::new("stdout", ""));
+    /// ```
+    pub fn clear(&mut self) {
+        self.content.clear();
+    }
+}
+
+/// Formatted output can be written directly into the content of a [File].
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::File;
+/// use std::fmt::Write;
+///
+/// let mut f = File::new("stdout", "");
+/// writeln!(f, "exit code: {}", 0).unwrap();
+///
+/// assert_eq!(f.content, "exit code: 0\n");
+/// ```
+impl fmt::Write for File {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.append_str(s);
+        Ok(())
+    }
 }
 
 impl fmt::Display for File {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "-- {} --", self.name)?;
-        write!(f, "{}", fix_trailing_newline(&self.content))
+        fmt_with_trailing_newline(f, &self.content)
     }
 }
 
@@ -371,65 +1813,116 @@ where
     U: Into<String>,
 {
     fn from((name, content): (T, U)) -> Self {
-        Self {
-            name: name.into(),
-            content: content.into(),
+        Self::new(name, content)
+    }
+}
+
+/// Check whether the given `txtar` string is already in the canonical form produced when
+/// formatting an [Archive].
+///
+/// This is useful for verifying that hand edited fixtures have been normalized, for example in a
+/// pre-commit hook.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::is_canonical;
+///
+/// assert!(is_canonical("comment\n-- a --\nfoo\n"));
+/// assert!(!is_canonical("comment\n--  a  --\nfoo\n")); // extra whitespace around the name
+/// assert!(!is_canonical("comment\n-- a --\nfoo")); // missing trailing newline
+/// ```
+pub fn is_canonical(s: &str) -> bool {
+    Archive::from(s).to_string() == s
+}
+
+// Display wrapper used for the exact serialization of an Archive
+struct Exact<'a>(&'a Archive);
+
+impl fmt::Display for Exact<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let files = &self.0.files;
+        write_exact_segment(f, &self.0.comment, files.is_empty())?;
+
+        for (i, file) in files.iter().enumerate() {
+            let last = i + 1 == files.len();
+            match parse::raw_marker(file, files.source(i)) {
+                Some(line) => {
+                    f.write_str(line)?;
+                    if !(line.ends_with('\n') || (last && file.content.is_empty())) {
+                        f.write_str("\n")?;
+                    }
+                }
+                None => writeln!(f, "{MARKER}{}{MARKER_END}", file.name)?,
+            }
+            let content = match file.content.strip_suffix('\n') {
+                Some(content) if last && !self.0.had_trailing_newline(i) => content,
+                _ => &file.content,
+            };
+            write_exact_segment(f, content, last)?;
         }
+
+        Ok(())
     }
 }
 
-fn fix_trailing_newline(s: &str) -> String {
-    let mut s = s.to_string();
-    if !(s.is_empty() || s.ends_with('\n')) {
-        s.push('\n');
+fn write_exact_segment(f: &mut fmt::Formatter<'_>, s: &str, last: bool) -> fmt::Result {
+    f.write_str(s)?;
+    if !last && missing_newline(s) {
+        f.write_str("\n")?;
     }
 
-    s
+    Ok(())
 }
 
-fn find_file_marker(s: &str) -> (String, Option<(&str, &str)>) {
-    let mut i = 0;
+fn fix_trailing_newline(s: &str) -> String {
+    if !missing_newline(s) {
+        return s.to_string();
+    }
 
-    loop {
-        let (before, after) = s.split_at(i);
-        let name_after = try_parse_marker(after);
-        if name_after.is_some() {
-            return (before.to_string(), name_after);
-        }
+    let mut fixed = String::with_capacity(s.len() + 1);
+    fixed.push_str(s);
+    fixed.push('\n');
 
-        match after.find(NEWLINE_MARKER) {
-            Some(j) => i += j + 1,
-            None => return (fix_trailing_newline(s), None),
-        };
-    }
+    fixed
 }
 
-fn try_parse_marker(s: &str) -> Option<(&str, &str)> {
-    if !s.starts_with(MARKER) {
-        return None;
+fn fix_trailing_newline_bytes(s: &[u8]) -> Vec<u8> {
+    let mut fixed = Vec::with_capacity(s.len() + 1);
+    fixed.extend_from_slice(s);
+    if missing_newline(s) {
+        fixed.push(b'\n');
     }
 
-    let (s, after) = match s.find('\n') {
-        Some(i) => {
-            let (s, after) = s.split_at(i);
-            (s, after.split_at(1).1) // consume the newline
-        }
-        None => (s, ""),
-    };
+    fixed
+}
+
+fn len_with_trailing_newline(s: &str) -> usize {
+    s.len() + missing_newline(s) as usize
+}
 
-    if !(s.ends_with(MARKER_END) && s.len() >= MARKER_LEN) {
-        return None;
+fn fmt_with_trailing_newline(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_str(s)?;
+    if missing_newline(s) {
+        f.write_str("\n")?;
     }
 
-    let (_, s) = s.split_at(MARKER.len());
-    let (s, _) = s.split_at(s.len() - MARKER_END.len());
+    Ok(())
+}
+
+fn write_with_trailing_newline(w: &mut impl Write, s: impl AsRef<[u8]>) -> io::Result<()> {
+    let s = s.as_ref();
+    w.write_all(s)?;
+    if missing_newline(s) {
+        w.write_all(b"\n")?;
+    }
 
-    Some((s.trim(), after))
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_dir::TestDir;
 
     const SIMPLE_ARCHIVE: &str = "\
 comment1
@@ -472,7 +1965,8 @@ hello world
                 File::new("empty", ""),
                 File::new("noNL", "hello world\n"),
                 File::new("empty filename line", "some content\n-- --\n"),
-            ],
+            ]
+            .into(),
         };
 
         let parsed = Archive::from(SIMPLE_ARCHIVE);
@@ -489,9 +1983,46 @@ hello world
                 File::new("file 2", "File 2 text.\n"),
                 File::new("empty", ""),
                 File::new("noNL", "hello world"),
-            ],
+            ]
+            .into(),
         };
 
         assert_eq!(a.to_string(), SIMPLE_FORMAT_OUTPUT); // trailing newline is enforced
     }
+
+    #[test]
+    fn to_file_round_trips_through_from_file() {
+        let dir = TestDir::new("to-file");
+        let path = dir.join("a.txtar");
+        let a = Archive::from(SIMPLE_ARCHIVE);
+
+        a.to_file(&path).unwrap();
+        let res = Archive::from_file(&path);
+
+        assert_eq!(res.unwrap(), a);
+    }
+
+    #[test]
+    fn lossy_parse_reports_each_invalid_line_once() {
+        let (a, invalid) = Archive::from_bytes_lossy(b"\xc3\n-- a --\n\xff\xfe x \xff\nok\n");
+
+        assert_eq!(a.comment(), "\u{fffd}\n");
+        assert_eq!(a["a"].content, "\u{fffd}\u{fffd} x \u{fffd}\nok\n");
+        assert_eq!(invalid, vec![1, 3]);
+    }
+
+    #[test]
+    fn serialized_len_matches_output() {
+        let inputs = [
+            "",
+            "\u{feff}c",
+            "c\n-- a --\n-- b --\nfoo",
+            "--  a  --\r\nfoo\r\n",
+        ];
+
+        for s in inputs {
+            let a = Archive::from(s);
+            assert_eq!(a.serialized_len(), a.to_string().len(), "{s:?}");
+        }
+    }
 }
@@ -0,0 +1,201 @@
+//! Checks for suspicious constructs within an [Archive].
+use crate::{
+    scan::{looks_like_marker, path_problem, PathProblem},
+    Archive,
+};
+use std::{collections::HashSet, error, fmt};
+
+/// A potential problem found within an [Archive] by [Archive::lint].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// The name of the file the problem was found in, or `None` for the archive comment
+    pub file: Option<String>,
+    /// The 1-based line number within the archive that the problem was found on, if known
+    pub line: Option<usize>,
+    /// The type of problem that was found
+    pub kind: LintKind,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {line}: ")?;
+        }
+        match &self.file {
+            Some(name) => write!(f, "{name:?}: {}", self.kind),
+            None => write!(f, "comment: {}", self.kind),
+        }
+    }
+}
+
+impl error::Error for Lint {}
+
+/// The specific problem identified by a [Lint].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LintKind {
+    /// The file has the same name as an earlier file in the archive
+    DuplicateName,
+    /// The file name is empty or only whitespace
+    EmptyName,
+    /// The file name is an absolute path
+    AbsolutePath,
+    /// The file name contains a `..` path component
+    ParentDir,
+    /// A line of content that looks like it was intended to be a file marker but is not valid
+    NearMissMarker(String),
+    /// The content contains a mixture of `\n` and `\r\n` line endings
+    MixedLineEndings,
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateName => write!(f, "duplicate file name"),
+            Self::EmptyName => write!(f, "empty file name"),
+            Self::AbsolutePath => write!(f, "file name is an absolute path"),
+            Self::ParentDir => write!(f, "file name contains a '..' component"),
+            Self::NearMissMarker(line) => write!(f, "line looks like a file marker: {line:?}"),
+            Self::MixedLineEndings => write!(f, "mixed line endings"),
+        }
+    }
+}
+
+impl Archive {
+    /// Check the archive for constructs that are valid but likely to be a mistake, returning a
+    /// [Lint] for each problem found in the order they appear in the archive.
+    ///
+    /// The checks performed are:
+    ///   - duplicate file names
+    ///   - empty file names
+    ///   - file names that are absolute paths or contain `..` components
+    ///   - lines in the comment or file content that look like they were intended to be file
+    ///     markers, such as `-- name--`
+    ///   - a mixture of `\n` and `\r\n` line endings within the comment or a file
+    ///
+    /// Line numbers are reported for archives that were parsed from text.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, LintKind};
+    ///
+    /// let a = Archive::from("-- ../a --\nfoo\n-- b--\n-- ../a --\n");
+    /// let kinds: Vec<LintKind> = a.lint().into_iter().map(|l| l.kind).collect();
+    ///
+    /// assert_eq!(
+    ///     kinds,
+    ///     vec![
+    ///         LintKind::ParentDir,
+    ///         LintKind::NearMissMarker("-- b--".to_string()),
+    ///         LintKind::DuplicateName,
+    ///         LintKind::ParentDir,
+    ///     ]
+    /// );
+    /// ```
+    pub fn lint(&self) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        lint_content(None, &self.comment, Some(1), &mut lints);
+
+        let mut seen = HashSet::new();
+        for (i, file) in self.files.iter().enumerate() {
+            let mut push = |kind| {
+                lints.push(Lint {
+                    file: Some(file.name.clone()),
+                    line: self.marker_line(i),
+                    kind,
+                })
+            };
+
+            if !seen.insert(file.name.as_str()) {
+                push(LintKind::DuplicateName);
+            }
+            if file.name.trim().is_empty() {
+                push(LintKind::EmptyName);
+            }
+            match path_problem(&file.name) {
+                Some(PathProblem::Absolute) => push(LintKind::AbsolutePath),
+                Some(PathProblem::ParentDir) => push(LintKind::ParentDir),
+                None => (),
+            }
+
+            lint_content(
+                Some(&file.name),
+                &file.content,
+                self.content_start_line(i),
+                &mut lints,
+            );
+        }
+
+        lints
+    }
+}
+
+fn lint_content(file: Option<&str>, content: &str, start: Option<usize>, lints: &mut Vec<Lint>) {
+    let mut crlf = false;
+    let mut lf = false;
+
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        match line.strip_suffix("\r\n") {
+            Some(_) => crlf = true,
+            None => lf |= line.ends_with('\n'),
+        }
+
+        let line = line.trim_end_matches(['\r', '\n']);
+        if looks_like_marker(line.as_bytes()) {
+            lints.push(Lint {
+                file: file.map(String::from),
+                line: start.map(|n| n + i),
+                kind: LintKind::NearMissMarker(line.to_string()),
+            });
+        }
+    }
+
+    if crlf && lf {
+        lints.push(Lint {
+            file: file.map(String::from),
+            line: None,
+            kind: LintKind::MixedLineEndings,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lints_report_archive_lines() {
+        let a = Archive::from("c\r\n-- b--\n-- /abs --\n\n--x --\n-- a --\r\n1\r\n2\n");
+        let found: Vec<_> = a
+            .lint()
+            .into_iter()
+            .map(|l| (l.file, l.line, l.kind))
+            .collect();
+
+        assert_eq!(
+            found,
+            vec![
+                (
+                    None,
+                    Some(2),
+                    LintKind::NearMissMarker("-- b--".to_string())
+                ),
+                (None, None, LintKind::MixedLineEndings),
+                (Some("/abs".to_string()), Some(3), LintKind::AbsolutePath),
+                (
+                    Some("/abs".to_string()),
+                    Some(5),
+                    LintKind::NearMissMarker("--x --".to_string())
+                ),
+                (Some("a".to_string()), None, LintKind::MixedLineEndings),
+            ]
+        );
+    }
+
+    #[test]
+    fn clean_archive_has_no_lints() {
+        let a = Archive::from("comment\n-- a/b.txt --\nfoo\n----\n-- c.txt --\nbar");
+
+        assert!(a.lint().is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+//! Converting between [Archive]s and Markdown documents.
+use crate::{Archive, File};
+
+impl Archive {
+    /// Render this archive as a Markdown document, with each file as a heading containing its
+    /// name followed by a fenced code block containing its content.
+    ///
+    /// The comment is written as-is at the start of the document. Each code block is tagged with
+    /// the extension of the file name, if it has one, so that it is highlighted when rendered, and
+    /// the fence is made long enough that it can not be closed by the content of the file. The
+    /// result can be converted back into an archive using [Archive::from_markdown], as long as
+    /// the comment does not contain any Markdown headings.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("A comment\n-- src/main.rs --\nfn main() {}\n-- README --\nhi\n");
+    ///
+    /// assert_eq!(
+    ///     a.to_markdown(),
+    ///     "A comment\n\n## `src/main.rs`\n\n```rs\nfn main() {}\n```\n\n## `README`\n\n```\nhi\n```\n"
+    /// );
+    /// ```
+    pub fn to_markdown(&self) -> String {
+        let mut s = String::new();
+        let comment = self.comment_without_bom();
+        if !comment.is_empty() {
+            s.push_str(comment);
+            if !comment.ends_with('\n') {
+                s.push('\n');
+            }
+        }
+
+        for f in self.files.iter() {
+            if !s.is_empty() {
+                s.push('\n');
+            }
+            let fence = "`".repeat(longest_backtick_run(&f.content).max(2) + 1);
+            let lang = extension(&f.name);
+            s.push_str(&format!("## `{}`\n\n{fence}{lang}\n{}", f.name, f.content));
+            if !f.content.is_empty() && !f.content.ends_with('\n') {
+                s.push('\n');
+            }
+            s.push_str(&fence);
+            s.push('\n');
+        }
+
+        s
+    }
+
+    /// Parse an [Archive] from a Markdown document in which each file is a heading containing its
+    /// name followed by a fenced code block containing its content.
+    ///
+    /// This accepts the output of [Archive::to_markdown] along with most hand written documents
+    /// in the same shape. The name of a file is the text of the heading, with any surrounding
+    /// backticks removed, and its content is the first fenced code block that follows. Any text
+    /// before the first heading becomes the comment, while other text and any headings that are
+    /// not followed by a code block are ignored. Code fences must not be indented.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let md = "\
+    /// Steps to reproduce:
+    ///
+    /// ### Cargo.toml
+    /// Put this in the root of the project:
+    /// ~~~toml
+    /// [package]
+    /// name = \"repro\"
+    /// ~~~
+    ///
+    /// ### `src/lib.rs`
+    /// ~~~rust
+    /// pub fn broken() {}
+    /// ~~~
+    /// ";
+    ///
+    /// assert_eq!(
+    ///     Archive::from_markdown(md).to_string(),
+    ///     "Steps to reproduce:\n-- Cargo.toml --\n[package]\nname = \"repro\"\n-- src/lib.rs --\npub fn broken() {}\n"
+    /// );
+    /// ```
+    pub fn from_markdown(s: &str) -> Self {
+        let mut comment = String::new();
+        let mut files = Vec::new();
+        let mut name: Option<String> = None;
+        let mut in_comment = true;
+        let mut lines = s.lines();
+
+        while let Some(line) = lines.next() {
+            if let Some(fence) = opening_fence(line) {
+                let mut content = String::new();
+                for line in lines.by_ref() {
+                    if is_closing_fence(line, fence) {
+                        break;
+                    }
+                    content.push_str(line);
+                    content.push('\n');
+                }
+
+                match name.take() {
+                    Some(name) => files.push(File::new(name, content)),
+                    // Code blocks in the comment are kept so that any headings inside of them
+                    // are not treated as file names.
+                    None if in_comment => {
+                        comment.push_str(&format!("{line}\n{content}{fence}\n"));
+                    }
+                    None => (),
+                }
+            } else if let Some(heading) = heading(line) {
+                name = Some(heading.to_string());
+                in_comment = false;
+            } else if in_comment {
+                comment.push_str(line);
+                comment.push('\n');
+            }
+        }
+
+        let comment = comment.trim_end_matches(['\n', '\r']);
+        let mut a = Archive::from_files(files);
+        if !comment.is_empty() {
+            a.comment = format!("{comment}\n");
+        }
+
+        a
+    }
+}
+
+fn longest_backtick_run(s: &str) -> usize {
+    s.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+fn extension(name: &str) -> &str {
+    let base = name.rsplit('/').next().unwrap_or(name);
+    match base.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => ext,
+        _ => "",
+    }
+}
+
+/// The text of an ATX heading with any surrounding backticks removed, if it is non-empty.
+fn heading(line: &str) -> Option<&str> {
+    let rest = line.trim_start_matches('#');
+    let level = line.len() - rest.len();
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+
+    let text = rest.trim();
+    let text = match text.strip_prefix('`').and_then(|t| t.strip_suffix('`')) {
+        Some(t) => t.trim(),
+        None => text,
+    };
+
+    (!text.is_empty()).then_some(text)
+}
+
+/// The fence that opens a fenced code block, without its info string.
+fn opening_fence(line: &str) -> Option<&str> {
+    let c = line.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let n = line.len() - line.trim_start_matches(c).len();
+    if n < 3 || (c == '`' && line[n..].contains('`')) {
+        return None;
+    }
+
+    Some(&line[..n])
+}
+
+fn is_closing_fence(line: &str, fence: &str) -> bool {
+    let line = line.trim_end();
+    let c = fence.as_bytes()[0];
+
+    line.len() >= fence.len() && line.bytes().all(|b| b == c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_round_trips() {
+        let a = Archive::from(
+            "comment\n\nmore comment\n-- a.md --\n# heading\n````\nfenced\n````\n-- .env --\n-- b --\nno newline",
+        );
+        let md = a.to_markdown();
+
+        assert!(md.contains("\n`````md\n"));
+        assert!(md.contains("\n## `.env`\n\n```\n```\n"));
+        assert_eq!(Archive::from_markdown(&md), a);
+    }
+
+    #[test]
+    fn code_blocks_in_the_comment_are_kept() {
+        let md = "intro\n```\n# not a heading\n```\n\n## a\n```\nfoo\n```\n```\nignored\n```\n";
+        let a = Archive::from_markdown(md);
+
+        assert_eq!(
+            a.to_string(),
+            "intro\n```\n# not a heading\n```\n-- a --\nfoo\n"
+        );
+    }
+}
@@ -0,0 +1,53 @@
+//! Parsing archives from memory-mapped files.
+use crate::Archive;
+use memmap2::Mmap;
+use std::{fs, io, path::Path, str};
+
+impl Archive {
+    /// Parse a `txtar` archive from the file at the specified path by memory-mapping it rather
+    /// than reading it into a `String`.
+    ///
+    /// For very large archives this avoids holding a full copy of the input in memory alongside
+    /// the parsed archive: only the parsed file contents are allocated. This will error if there
+    /// are any issues with reading the file or if its contents are not valid UTF-8.
+    ///
+    /// The file must not be modified by another process while it is being parsed.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from_file_mmap("corpus.txtar").unwrap();
+    /// ```
+    pub fn from_file_mmap(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = fs::File::open(path)?;
+        if file.metadata()?.len() == 0 {
+            return Ok(Self::default());
+        }
+
+        // SAFETY: the map is only read for the duration of this call and the caller is required
+        // not to modify the underlying file while it is being parsed.
+        let map = unsafe { Mmap::map(&file)? };
+        let s = str::from_utf8(&map).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(Self::from(s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn mmap_matches_from_file() {
+        let dir = TestDir::new("mmap");
+        let path = dir.join("a.txtar");
+        let a = Archive::from("comment\n-- a --\nfoo\n-- b --\nbar\n");
+
+        a.to_file(&path).unwrap();
+        let res = Archive::from_file_mmap(&path);
+
+        assert_eq!(res.unwrap(), a);
+    }
+}
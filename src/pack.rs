@@ -0,0 +1,546 @@
+//! Building an [Archive] from files on disk.
+use crate::{
+    binary::Binary,
+    filter::Filter,
+    progress::{Progress, ProgressFn},
+    Archive, ByteArchive, ByteFile,
+};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Configuration for reading a directory into an [Archive] using [Archive::from_dir_with].
+///
+/// The default options match the behaviour of [Archive::from_dir].
+///
+/// ## Example
+/// ```no_run
+/// use simple_txtar::{Archive, PackOptions};
+///
+/// let (a, _) = Archive::from_dir_with(
+///     "testdata",
+///     PackOptions::new().on_progress(|p| eprintln!("[{}/{}] {}", p.index + 1, p.total, p.name)),
+/// )
+/// .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct PackOptions {
+    follow_symlinks: bool,
+    hidden: bool,
+    max_depth: Option<usize>,
+    max_file_size: Option<u64>,
+    binary: Binary,
+    #[cfg(feature = "ignore")]
+    pub(crate) gitignore: bool,
+    #[cfg(feature = "ignore")]
+    pub(crate) ignore: Vec<String>,
+    on_progress: Option<Arc<ProgressFn>>,
+}
+
+impl Default for PackOptions {
+    fn default() -> Self {
+        Self {
+            follow_symlinks: false,
+            hidden: true,
+            max_depth: None,
+            max_file_size: None,
+            binary: Binary::Error,
+            #[cfg(feature = "ignore")]
+            gitignore: false,
+            #[cfg(feature = "ignore")]
+            ignore: Vec::new(),
+            on_progress: None,
+        }
+    }
+}
+
+impl PackOptions {
+    /// Construct a new [PackOptions] with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the targets of symlinks as if they were regular files and directories.
+    ///
+    /// Symlinks to directories that are already being read are skipped to avoid following
+    /// cycles. Defaults to `false`, which skips all symlinks.
+    pub fn follow_symlinks(&mut self, follow: bool) -> &mut Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Include files and directories whose names start with a `.`. Defaults to `true`.
+    pub fn hidden(&mut self, hidden: bool) -> &mut Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// The number of levels of subdirectories to read, with `0` only reading the files directly
+    /// inside of `dir`. Defaults to no limit.
+    pub fn max_depth(&mut self, depth: usize) -> &mut Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Skip files that are larger than the given number of bytes. Defaults to no limit.
+    pub fn max_file_size(&mut self, bytes: u64) -> &mut Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// How to handle files whose content is not valid UTF-8. Defaults to [Binary::Error].
+    pub fn binary(&mut self, binary: Binary) -> &mut Self {
+        self.binary = binary;
+        self
+    }
+
+    /// Skip any files and directories that are ignored by a `.gitignore` file in `dir` or any of
+    /// its subdirectories.
+    ///
+    /// Only `.gitignore` files within `dir` are used, regardless of whether or not it is inside
+    /// of a git repository. Defaults to `false`.
+    #[cfg(feature = "ignore")]
+    pub fn gitignore(&mut self, gitignore: bool) -> &mut Self {
+        self.gitignore = gitignore;
+        self
+    }
+
+    /// Skip any files and directories matching the given pattern, which uses the same syntax as
+    /// a line of a `.gitignore` file located in `dir`.
+    ///
+    /// Patterns take priority over any `.gitignore` files, and a pattern starting with `!` can be
+    /// used to include files that would otherwise be ignored. Invalid patterns result in an error
+    /// of kind [io::ErrorKind::InvalidInput] when the directory is read.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::{Archive, PackOptions};
+    ///
+    /// let (a, _) = Archive::from_dir_with(
+    ///     "testdata",
+    ///     PackOptions::new().gitignore(true).ignore("target/").ignore("*~"),
+    /// )
+    /// .unwrap();
+    /// ```
+    #[cfg(feature = "ignore")]
+    pub fn ignore(&mut self, pattern: impl Into<String>) -> &mut Self {
+        self.ignore.push(pattern.into());
+        self
+    }
+
+    /// Call `f` with the [Progress] of each file after it has been read.
+    ///
+    /// The directory is walked before any files are read so that the total number of files is
+    /// known up front.
+    pub fn on_progress(&mut self, f: impl Fn(Progress<'_>) + Send + Sync + 'static) -> &mut Self {
+        self.on_progress = Some(Arc::new(f));
+        self
+    }
+}
+
+impl fmt::Debug for PackOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("PackOptions");
+        d.field("follow_symlinks", &self.follow_symlinks)
+            .field("hidden", &self.hidden)
+            .field("max_depth", &self.max_depth)
+            .field("max_file_size", &self.max_file_size)
+            .field("binary", &self.binary);
+        #[cfg(feature = "ignore")]
+        d.field("gitignore", &self.gitignore)
+            .field("ignore", &self.ignore);
+        d.field("on_progress", &self.on_progress.is_some()).finish()
+    }
+}
+
+/// A summary of the entries skipped when reading a directory using [Archive::from_dir_with].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PackReport {
+    /// Each file or directory that was not read, in the order it was found
+    pub skipped: Vec<Skipped>,
+}
+
+/// A file or directory that was skipped when reading a directory into an [Archive].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skipped {
+    /// The path of the skipped entry
+    pub path: PathBuf,
+    /// Why the entry was skipped
+    pub reason: SkipReason,
+}
+
+/// The reason that a [Skipped] entry was not read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SkipReason {
+    /// The entry is a symlink and [PackOptions::follow_symlinks] is not set
+    Symlink,
+    /// The entry is a symlink to a directory that is already being read
+    SymlinkCycle,
+    /// The entry is hidden and [PackOptions::hidden] is set to `false`
+    Hidden,
+    /// The entry is a directory below [PackOptions::max_depth]
+    MaxDepth,
+    /// The entry is a file larger than [PackOptions::max_file_size], which is of the given size
+    TooLarge(u64),
+    /// The entry matches an ignore pattern or `.gitignore` file
+    Ignored,
+    /// The entry is a file that is not valid UTF-8 and [PackOptions::binary] is [Binary::Skip]
+    Binary,
+}
+
+impl Archive {
+    /// Recursively read the text files under the given directory into a new [Archive].
+    ///
+    /// Each file is named by its path relative to `dir` using `/` as the separator. Directory
+    /// entries are visited in order of their names so that the output is stable across runs and
+    /// platforms. Symlinks are skipped. This will error if `dir` can not be read or if any file
+    /// or path is not valid UTF-8: use [ByteArchive::from_dir] for directories that may contain
+    /// non UTF-8 paths or content.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from_dir("testdata").unwrap();
+    /// a.to_file("testdata.txtar").unwrap();
+    /// ```
+    pub fn from_dir(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let (a, _) = Self::from_dir_with(dir, &PackOptions::default())?;
+
+        Ok(a)
+    }
+
+    /// Recursively read the text files under the given directory into a new [Archive] using the
+    /// provided [PackOptions].
+    ///
+    /// Files are named and ordered in the same way as [Archive::from_dir]. Along with the
+    /// archive, a [PackReport] is returned listing any entries that were skipped.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::{Archive, PackOptions};
+    ///
+    /// let (a, report) = Archive::from_dir_with(
+    ///     "testdata",
+    ///     PackOptions::new().hidden(false).max_file_size(1024 * 1024),
+    /// )
+    /// .unwrap();
+    ///
+    /// for s in report.skipped {
+    ///     eprintln!("skipped {}: {:?}", s.path.display(), s.reason);
+    /// }
+    /// ```
+    pub fn from_dir_with(
+        dir: impl AsRef<Path>,
+        opts: &PackOptions,
+    ) -> io::Result<(Self, PackReport)> {
+        let dir = dir.as_ref();
+        let mut w = Walker {
+            opts,
+            filter: Filter::new(dir, opts)?,
+            ancestors: Vec::new(),
+            paths: Vec::new(),
+            report: PackReport::default(),
+        };
+        w.walk(dir, "", 0)?;
+        let Walker {
+            paths, mut report, ..
+        } = w;
+
+        let total = paths.len();
+        let mut a = Archive::with_capacity(total);
+        for (index, (name, path)) in paths.into_iter().enumerate() {
+            let bytes = fs::read(&path)?;
+            if let Some(f) = &opts.on_progress {
+                f(Progress {
+                    name: &name,
+                    index,
+                    total,
+                    bytes: bytes.len() as u64,
+                });
+            }
+
+            let Some(file) = opts.binary.file(name, bytes, &path)? else {
+                report.skipped.push(Skipped {
+                    path,
+                    reason: SkipReason::Binary,
+                });
+                continue;
+            };
+            a.files.push(file);
+        }
+
+        Ok((a, report))
+    }
+}
+
+impl ByteArchive {
+    /// Recursively read the files under the given directory into a new [ByteArchive].
+    ///
+    /// This behaves in the same way as [Archive::from_dir] but neither the paths nor the contents
+    /// of the files are required to be valid UTF-8. See [ByteFile::from_path] for how file names
+    /// are encoded.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::ByteArchive;
+    ///
+    /// let a = ByteArchive::from_dir("testdata").unwrap();
+    /// for f in a.iter() {
+    ///     println!("{}", f.path().display());
+    /// }
+    /// ```
+    pub fn from_dir(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let mut a = ByteArchive::default();
+        pack_dir_bytes(dir.as_ref(), &[], &mut a)?;
+
+        Ok(a)
+    }
+}
+
+/// The state of a traversal of a directory by [Archive::from_dir_with].
+struct Walker<'a> {
+    opts: &'a PackOptions,
+    filter: Filter,
+    /// The canonical paths of the directories currently being read, when following symlinks
+    ancestors: Vec<PathBuf>,
+    /// The name and path of every file to be read, in order
+    paths: Vec<(String, PathBuf)>,
+    report: PackReport,
+}
+
+impl Walker<'_> {
+    fn walk(&mut self, dir: &Path, prefix: &str, depth: usize) -> io::Result<()> {
+        if self.opts.follow_symlinks {
+            self.ancestors.push(fs::canonicalize(dir)?);
+        }
+        self.filter.enter(dir);
+
+        let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|e| e.file_name());
+
+        for entry in entries {
+            let file_name = entry.file_name();
+            let path = entry.path();
+            let name = match file_name.to_str() {
+                Some(s) => s,
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("path is not valid UTF-8: {path:?}"),
+                    ))
+                }
+            };
+
+            let mut file_type = entry.file_type()?;
+            if !self.opts.hidden && name.starts_with('.') {
+                self.skip(path, SkipReason::Hidden);
+                continue;
+            }
+            if self.filter.is_ignored(&path, file_type.is_dir()) {
+                self.skip(path, SkipReason::Ignored);
+                continue;
+            }
+
+            if file_type.is_symlink() {
+                if !self.opts.follow_symlinks {
+                    self.skip(path, SkipReason::Symlink);
+                    continue;
+                }
+                file_type = fs::metadata(&path)?.file_type();
+                if file_type.is_dir() && self.ancestors.contains(&fs::canonicalize(&path)?) {
+                    self.skip(path, SkipReason::SymlinkCycle);
+                    continue;
+                }
+            }
+
+            if file_type.is_dir() {
+                if self.opts.max_depth.is_some_and(|max| depth >= max) {
+                    self.skip(path, SkipReason::MaxDepth);
+                    continue;
+                }
+                self.walk(&path, &format!("{prefix}{name}/"), depth + 1)?;
+            } else if file_type.is_file() {
+                let len = fs::metadata(&path)?.len();
+                if self.opts.max_file_size.is_some_and(|max| len > max) {
+                    self.skip(path, SkipReason::TooLarge(len));
+                    continue;
+                }
+                self.paths.push((format!("{prefix}{name}"), path));
+            }
+        }
+
+        self.filter.leave();
+        if self.opts.follow_symlinks {
+            self.ancestors.pop();
+        }
+
+        Ok(())
+    }
+
+    fn skip(&mut self, path: PathBuf, reason: SkipReason) {
+        self.report.skipped.push(Skipped { path, reason });
+    }
+}
+
+fn pack_dir_bytes(dir: &Path, prefix: &[u8], a: &mut ByteArchive) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let mut name = prefix.to_vec();
+        name.extend_from_slice(entry.file_name().as_encoded_bytes());
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            name.push(b'/');
+            pack_dir_bytes(&entry.path(), &name, a)?;
+        } else if file_type.is_file() {
+            a.add_file(ByteFile::new(name, fs::read(entry.path())?));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn from_dir_round_trips_through_materialize() {
+        let dir = TestDir::new("pack");
+        let a = Archive::from("-- b.txt --\nfoo\n-- a/z.txt --\nbar\n-- a/b/c.txt --\nbaz");
+
+        a.materialize(&dir).unwrap();
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let s = seen.clone();
+        let res = Archive::from_dir_with(
+            &dir,
+            PackOptions::new().on_progress(move |p| {
+                s.lock()
+                    .unwrap()
+                    .push((p.name.to_string(), p.index, p.total))
+            }),
+        );
+
+        let expected = Archive::from("-- a/b/c.txt --\nbaz\n-- a/z.txt --\nbar\n-- b.txt --\nfoo");
+        assert_eq!(res.unwrap().0, expected);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                ("a/b/c.txt".to_string(), 0, 3),
+                ("a/z.txt".to_string(), 1, 3),
+                ("b.txt".to_string(), 2, 3),
+            ]
+        );
+    }
+
+    #[cfg(feature = "ignore")]
+    #[test]
+    fn ignored_paths_are_skipped() {
+        let dir = TestDir::new("ignore");
+        let a = Archive::from(
+            "-- .gitignore --\n*.log\n-- a.txt --\n-- a.txt~ --\n-- b.log --\n\
+             -- sub/.gitignore --\n!keep.log\n-- sub/keep.log --\n-- sub/c.log --\n\
+             -- target/d.txt --\n",
+        );
+
+        a.materialize(&dir).unwrap();
+        let res = Archive::from_dir_with(
+            &dir,
+            PackOptions::new()
+                .gitignore(true)
+                .ignore("target/")
+                .ignore("*~"),
+        );
+
+        let (a, report) = res.unwrap();
+        let names: Vec<_> = a.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![".gitignore", "a.txt", "sub/.gitignore", "sub/keep.log"]
+        );
+        assert_eq!(report.skipped.len(), 4);
+        assert!(report
+            .skipped
+            .iter()
+            .all(|s| s.reason == SkipReason::Ignored));
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn binary_files_round_trip_as_base64() {
+        let dir = TestDir::new("binary");
+        let bytes: Vec<u8> = (0..=255).collect();
+        fs::write(dir.join("a.bin"), &bytes).unwrap();
+        fs::write(dir.join("b.txt"), "text\n").unwrap();
+
+        let strict = Archive::from_dir(&dir);
+        let (skipped, report) =
+            Archive::from_dir_with(&dir, PackOptions::new().binary(Binary::Skip)).unwrap();
+        let (encoded, _) =
+            Archive::from_dir_with(&dir, PackOptions::new().binary(Binary::Base64)).unwrap();
+
+        encoded
+            .materialize_with(
+                dir.join("restored"),
+                crate::ExtractOptions::new().decode_base64(true),
+            )
+            .unwrap();
+        let restored = fs::read(dir.join("restored/a.bin"));
+
+        assert_eq!(strict.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(report.skipped[0].reason, SkipReason::Binary);
+        assert_eq!(encoded[0].name, "a.bin.base64");
+        assert_eq!(restored.unwrap(), bytes);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn traversal_options_are_reported() {
+        let dir = TestDir::new("walk");
+        let a = Archive::from(
+            "-- .hidden --\n-- a/b/deep.txt --\n-- a/ok.txt --\n-- big.txt --\n0123456789\n",
+        );
+        a.materialize(&dir).unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("a/loop")).unwrap();
+        std::os::unix::fs::symlink(dir.join("a/ok.txt"), dir.join("link.txt")).unwrap();
+
+        let res = Archive::from_dir_with(
+            &dir,
+            PackOptions::new()
+                .follow_symlinks(true)
+                .hidden(false)
+                .max_depth(1)
+                .max_file_size(8),
+        );
+        let (default, default_report) = Archive::from_dir_with(&dir, &PackOptions::new()).unwrap();
+
+        let (a, report) = res.unwrap();
+        let names: Vec<_> = a.iter().map(|f| f.name.as_str()).collect();
+        let reasons: Vec<_> = report.skipped.iter().map(|s| s.reason).collect();
+        assert_eq!(names, vec!["a/ok.txt", "link.txt"]);
+        assert_eq!(
+            reasons,
+            vec![
+                SkipReason::Hidden,
+                SkipReason::MaxDepth,
+                SkipReason::SymlinkCycle,
+                SkipReason::TooLarge(11),
+            ]
+        );
+
+        assert_eq!(default.len(), 4);
+        assert_eq!(default_report.skipped.len(), 2);
+        assert!(default_report
+            .skipped
+            .iter()
+            .all(|s| s.reason == SkipReason::Symlink));
+    }
+}
@@ -0,0 +1,131 @@
+//! Parallel parsing and extraction of large archives using [rayon].
+use crate::{
+    extract::Target, fix_trailing_newline, parse::locate_files, scan::strip_bom, Archive, Error,
+    ExtractOptions, ExtractReport, ParseOptions,
+};
+use rayon::prelude::*;
+use std::{
+    collections::HashSet,
+    fs, io,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+impl Archive {
+    /// Parse a `txtar` archive, copying the content of each file into the archive in parallel.
+    ///
+    /// The input is first scanned for file markers on the current thread, which does not copy
+    /// any content, before the files themselves are built in parallel using the global [rayon]
+    /// thread pool. The result is identical to that of [Archive::from] with the files in the same
+    /// order as the input, but can be significantly faster for archives containing many
+    /// thousands of files.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let s: String = (0..10_000).map(|i| format!("-- {i}.txt --\n{i}\n")).collect();
+    /// let a = Archive::from_str_par(&s);
+    ///
+    /// assert_eq!(a, Archive::from(s.as_str()));
+    /// ```
+    pub fn from_str_par(s: &str) -> Self {
+        let body = strip_bom(s);
+        let opts = ParseOptions::default();
+        let (comment, entries) = locate_files(body, s.len() - body.len(), false);
+        let entries: Vec<_> = entries.collect();
+
+        Self {
+            comment: fix_trailing_newline(comment),
+            files: entries
+                .into_par_iter()
+                .map(|e| e.into_parsed(&opts, &fix_trailing_newline))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+/// Write files concurrently using `threads` threads, or the global thread pool if `threads` is
+/// zero, creating all of the required directories up front.
+pub(crate) fn write_files(
+    to_write: Vec<Target<'_>>,
+    threads: usize,
+    opts: &ExtractOptions,
+    mut report: ExtractReport,
+) -> Result<ExtractReport, Error> {
+    let mut seen = HashSet::new();
+    let mut to_write: Vec<_> = to_write
+        .into_iter()
+        .rev()
+        .filter(|t| seen.insert(t.path.clone()))
+        .collect();
+    to_write.reverse();
+
+    for t in to_write.iter() {
+        report.create_parent_dirs(&t.path)?;
+    }
+
+    let total = to_write.len();
+    let done = AtomicUsize::new(0);
+    let write = || {
+        to_write
+            .par_iter()
+            .map(|t| {
+                fs::write(&t.path, &t.content)?;
+                opts.finish_file(t, done.fetch_add(1, Ordering::Relaxed), total)?;
+
+                Ok(t.content.len() as u64)
+            })
+            .collect::<io::Result<Vec<_>>>()
+    };
+    let written = match threads {
+        0 => write()?,
+        n => rayon::ThreadPoolBuilder::new()
+            .num_threads(n)
+            .build()
+            .map_err(io::Error::other)?
+            .install(write)?,
+    };
+
+    report.files_written += written.len();
+    report.bytes_written += written.iter().sum::<u64>();
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn parallel_parse_preserves_order_and_positions() {
+        let s: String = (0..1000).map(|i| format!("-- {i} --\n{i}\n")).collect();
+        let s = format!("\u{feff}comment\n{s}-- last --\nno newline");
+        let par = Archive::from_str_par(&s);
+        let seq = Archive::from(s.as_str());
+
+        assert_eq!(par, seq);
+        assert_eq!(par.files.sources(), seq.files.sources());
+    }
+
+    #[test]
+    fn parallel_extraction_matches_sequential() {
+        let tmp = TestDir::new("par");
+        let dir = tmp.join("out");
+        let s: String = (0..500)
+            .map(|i| format!("-- {}/{i}.txt --\n{i}\n", i % 7))
+            .collect();
+        let a = Archive::from(format!("{s}-- 0/0.txt --\nlast\n"));
+
+        let report = a
+            .materialize_with(&dir, crate::ExtractOptions::new().parallelism(4))
+            .unwrap();
+        let res = Archive::from_dir(&dir);
+
+        assert_eq!(report.files_written, 500);
+        assert_eq!(report.dirs_created, 8);
+        assert_eq!(res.unwrap()["0/0.txt"].content, "last\n");
+    }
+}
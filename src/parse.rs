@@ -0,0 +1,860 @@
+//! Parsing of `txtar` archives from text.
+use crate::{
+    fix_trailing_newline,
+    index::Files,
+    scan::{
+        count_newlines, looks_like_marker, split_segments, strip_bom, try_parse_marker, Marker,
+        Segments,
+    },
+    Archive, DuplicateName, File, ParseError, ParseErrorKind, Source,
+};
+use std::{borrow::Cow, collections::HashSet, fmt};
+
+/// How files that share a name with another file in the same archive are handled when parsing or
+/// when calling [Archive::dedup].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Duplicates {
+    /// Keep every file: [Archive::get] will return the first file with a given name
+    #[default]
+    KeepAll,
+    /// Keep only the first file with a given name, dropping any later files with the same name
+    KeepFirst,
+    /// Keep only the last file with a given name, dropping any earlier files with the same name
+    KeepLast,
+    /// Return an error for the first file that reuses the name of an earlier file
+    Error,
+}
+
+/// Configuration for parsing a `txtar` archive using [Archive::from_str_with].
+///
+/// The default options match the behaviour of the [From] implementations for [Archive].
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::{Archive, Duplicates, ParseOptions};
+///
+/// let s = "-- a --\r\nfoo\r\n-- a --\r\nbar\r\n";
+/// let a = Archive::from_str_with(
+///     s,
+///     ParseOptions::new()
+///         .normalize_newlines(true)
+///         .duplicates(Duplicates::KeepLast),
+/// )
+/// .unwrap();
+///
+/// assert_eq!(a.to_string(), "-- a --\nbar\n");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    lossless: bool,
+    normalize_newlines: bool,
+    strip_bom: bool,
+    trim_names: bool,
+    lenient_markers: bool,
+    duplicates: Duplicates,
+    strict: bool,
+    limits: Limits,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            lossless: false,
+            normalize_newlines: false,
+            strip_bom: true,
+            trim_names: true,
+            lenient_markers: false,
+            duplicates: Duplicates::KeepAll,
+            strict: false,
+            limits: Limits::default(),
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Construct a new [ParseOptions] with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record enough information about the input for [Archive::to_string_exact] to reproduce it
+    /// byte-for-byte.
+    ///
+    /// In lossless mode the original file marker lines are retained and no trailing newline is
+    /// added to the final comment or file content if the input did not end with one. Any byte
+    /// order mark is left at the start of the comment and line endings are not normalized,
+    /// regardless of the other options that have been set. Defaults to `false`.
+    pub fn lossless(&mut self, lossless: bool) -> &mut Self {
+        self.lossless = lossless;
+        self
+    }
+
+    /// Convert all `\r\n` line endings in the input to `\n` before parsing.
+    ///
+    /// File markers ending in `\r\n` are always recognised, but without this option any `\r\n`
+    /// line endings in the comment or file content are preserved. Defaults to `false`.
+    pub fn normalize_newlines(&mut self, normalize: bool) -> &mut Self {
+        self.normalize_newlines = normalize;
+        self
+    }
+
+    /// Remove a UTF-8 byte order mark from the start of the input before parsing.
+    ///
+    /// Without this, a byte order mark will be included at the start of the comment or prevent
+    /// a file marker on the first line from being recognised. Defaults to `true`.
+    pub fn strip_bom(&mut self, strip: bool) -> &mut Self {
+        self.strip_bom = strip;
+        self
+    }
+
+    /// Strip any additional whitespace surrounding file names within file markers.
+    ///
+    /// This is the behaviour described by the `txtar` format and defaults to `true`. When set to
+    /// `false` only the single spaces that are part of the `"-- "` and `" --"` marker sequences
+    /// are removed.
+    pub fn trim_names(&mut self, trim: bool) -> &mut Self {
+        self.trim_names = trim;
+        self
+    }
+
+    /// Also accept file markers that do not exactly follow the `"-- NAME --"` form but were most
+    /// likely intended to be file markers, such as `"--- NAME ---"`, `"-- NAME--"` or `"--NAME --"`.
+    ///
+    /// A line is accepted as a lenient file marker if it begins and ends with at least two dashes
+    /// and contains something other than dashes and whitespace between them. All surrounding
+    /// dashes and whitespace are stripped from the name. Without this option such lines are
+    /// treated as part of the comment or file content. Defaults to `false`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, ParseOptions};
+    ///
+    /// let s = "--- a.txt ---\nfoo\n--b.txt--\nbar\n";
+    /// let a = Archive::from_str_with(s, ParseOptions::new().lenient_markers(true)).unwrap();
+    ///
+    /// assert_eq!(a.to_string(), "-- a.txt --\nfoo\n-- b.txt --\nbar\n");
+    /// assert_eq!(Archive::from(s).comment(), s);
+    /// ```
+    pub fn lenient_markers(&mut self, lenient: bool) -> &mut Self {
+        self.lenient_markers = lenient;
+        self
+    }
+
+    /// Set the [Limits] to check the input against before parsing.
+    ///
+    /// Defaults to no limits.
+    pub fn limits(&mut self, limits: Limits) -> &mut Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set the policy for handling files that share the same name.
+    ///
+    /// Defaults to [Duplicates::KeepAll].
+    pub fn duplicates(&mut self, policy: Duplicates) -> &mut Self {
+        self.duplicates = policy;
+        self
+    }
+
+    /// Return a [ParseError] for file markers with an empty name and for lines that look like
+    /// they were intended to be file markers but are not valid.
+    ///
+    /// Defaults to `false`.
+    pub fn strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+}
+
+/// Limits on the size of a `txtar` archive, checked before any file content is copied out of the
+/// input.
+///
+/// By default no limits are applied. See [Archive::from_str_limited] for parsing input from an
+/// untrusted source.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Limits {
+    max_files: Option<usize>,
+    max_total_bytes: Option<usize>,
+    max_file_bytes: Option<usize>,
+}
+
+impl Limits {
+    /// Construct a new [Limits] with no limits set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The maximum number of files that the archive may contain.
+    pub fn max_files(&mut self, max: usize) -> &mut Self {
+        self.max_files = Some(max);
+        self
+    }
+
+    /// The maximum size of the complete input in bytes.
+    pub fn max_total_bytes(&mut self, max: usize) -> &mut Self {
+        self.max_total_bytes = Some(max);
+        self
+    }
+
+    /// The maximum size of the content of any single file in bytes.
+    pub fn max_file_bytes(&mut self, max: usize) -> &mut Self {
+        self.max_file_bytes = Some(max);
+        self
+    }
+}
+
+impl Archive {
+    /// Parse a `txtar` archive using the provided [ParseOptions].
+    ///
+    /// An error can only be returned if [ParseOptions::strict] is enabled, the duplicate policy
+    /// is set to [Duplicates::Error] or the input exceeds the configured [Limits].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, ParseOptions};
+    ///
+    /// let s = "--   padded name   --\nfoo";
+    ///
+    /// let a = Archive::from_str_with(s, &ParseOptions::new()).unwrap();
+    /// assert_eq!(a[0].name, "padded name");
+    ///
+    /// let a = Archive::from_str_with(s, ParseOptions::new().trim_names(false)).unwrap();
+    /// assert_eq!(a[0].name, "  padded name  ");
+    /// ```
+    pub fn from_str_with(s: &str, opts: &ParseOptions) -> Result<Self, ParseError> {
+        if let Some(max) = opts.limits.max_total_bytes {
+            if s.len() > max {
+                let kind = ParseErrorKind::InputTooLarge { max };
+                return Err(ParseError { line: 1, kind });
+            }
+        }
+
+        let body = if opts.strip_bom && !opts.lossless {
+            strip_bom(s)
+        } else {
+            s
+        };
+        let offset = s.len() - body.len();
+        let body = if opts.normalize_newlines && !opts.lossless {
+            Cow::Owned(body.replace("\r\n", "\n"))
+        } else {
+            Cow::Borrowed(body)
+        };
+
+        if opts.limits.max_files.is_some() || opts.limits.max_file_bytes.is_some() {
+            check_limits(&body, opts)?;
+        }
+        if opts.strict || opts.duplicates == Duplicates::Error {
+            check_lines(&body, opts)?;
+        }
+
+        let mut a = parse(&body, offset, opts);
+        dedup(&mut a.files, opts.duplicates);
+
+        Ok(a)
+    }
+
+    /// Parse a `txtar` archive from an untrusted source, returning an error if the input exceeds
+    /// the given [Limits].
+    ///
+    /// This is equivalent to calling [Archive::from_str_with] with only [ParseOptions::limits]
+    /// set. The number and size of the files in the input are checked before any content is
+    /// copied, so oversized input is rejected without allocating space for it.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, Limits, ParseErrorKind};
+    ///
+    /// let s = "-- a --\nfoo\n-- b --\nbar\n-- c --\nbaz\n";
+    ///
+    /// let err = Archive::from_str_limited(s, Limits::new().max_files(2)).unwrap_err();
+    /// assert_eq!(err.kind, ParseErrorKind::TooManyFiles { max: 2 });
+    /// assert_eq!(err.line, 5);
+    ///
+    /// let err = Archive::from_str_limited(s, Limits::new().max_total_bytes(16)).unwrap_err();
+    /// assert_eq!(err.kind, ParseErrorKind::InputTooLarge { max: 16 });
+    ///
+    /// assert!(Archive::from_str_limited(s, Limits::new().max_file_bytes(4)).is_ok());
+    /// ```
+    pub fn from_str_limited(s: &str, limits: &Limits) -> Result<Self, ParseError> {
+        Self::from_str_with(s, ParseOptions::new().limits(limits.clone()))
+    }
+
+    /// Parse a `txtar` archive, returning an error for constructs that are technically valid but
+    /// most likely a mistake.
+    ///
+    /// The `txtar` format itself has no syntax errors, so the [From] implementations will accept
+    /// any input. When validating archives from an untrusted or hand edited source, this method
+    /// can be used instead to reject:
+    ///   - file markers that have an empty name
+    ///   - file markers that reuse the name of an earlier file
+    ///   - lines beginning and ending with `--` that are not valid file markers, such as
+    ///     `-- name--`, which would otherwise be silently treated as file content
+    ///
+    /// This is equivalent to calling [Archive::from_str_with] with [ParseOptions::strict]
+    /// enabled and a duplicate policy of [Duplicates::Error].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, ParseErrorKind};
+    ///
+    /// let s = "-- file1 --\nfoo\n-- file2--\nbar";
+    /// let err = Archive::parse_strict(s).unwrap_err();
+    ///
+    /// assert_eq!(err.line, 3);
+    /// assert_eq!(err.kind, ParseErrorKind::MalformedMarker("-- file2--".to_string()));
+    ///
+    /// let s = "-- file1 --\nfoo\n-- file2 --\nbar";
+    /// assert_eq!(Archive::parse_strict(s), Ok(Archive::from(s)));
+    /// ```
+    pub fn parse_strict(s: &str) -> Result<Self, ParseError> {
+        Self::from_str_with(
+            s,
+            ParseOptions::new()
+                .strict(true)
+                .duplicates(Duplicates::Error),
+        )
+    }
+
+    /// Apply a [Duplicates] policy to the files already in the archive.
+    ///
+    /// With [Duplicates::Error] the archive is left unchanged and the first file that reuses the
+    /// name of an earlier file is returned as a [DuplicateName] error.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, Duplicates};
+    ///
+    /// let mut a = Archive::from("-- a --\n1\n-- b --\n2\n-- a --\n3\n");
+    /// assert_eq!(a.dedup(Duplicates::Error).unwrap_err().file.content, "3\n");
+    ///
+    /// a.dedup(Duplicates::KeepLast).unwrap();
+    /// assert_eq!(a.len(), 2);
+    /// assert_eq!(a["a"].content, "3\n");
+    /// ```
+    pub fn dedup(&mut self, policy: Duplicates) -> Result<(), DuplicateName> {
+        if policy == Duplicates::Error {
+            let mut seen = HashSet::new();
+            return match self.files.iter().find(|f| !seen.insert(&f.name)) {
+                Some(file) => Err(DuplicateName { file: file.clone() }),
+                None => Ok(()),
+            };
+        }
+
+        dedup(&mut self.files, policy);
+
+        Ok(())
+    }
+}
+
+/// Lazily iterate over the files in a `txtar` archive without building an [Archive].
+///
+/// Each item is the name and content of a file, borrowed from the input. Files are located as the
+/// iterator is advanced, so finding a single file or streaming through an archive does not
+/// require allocating every file up front. The comment is skipped.
+///
+/// Names are trimmed in the same way as when parsing an [Archive] but content is returned exactly
+/// as it appears in the input, meaning that the content of the final file may be missing its
+/// trailing newline.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::parse_iter;
+///
+/// let s = "comment\n-- a.txt --\nfoo\n-- b.txt --\nbar";
+///
+/// assert_eq!(parse_iter(s).find(|(name, _)| *name == "a.txt"), Some(("a.txt", "foo\n")));
+/// assert_eq!(
+///     parse_iter(s).collect::<Vec<_>>(),
+///     vec![("a.txt", "foo\n"), ("b.txt", "bar")]
+/// );
+/// ```
+pub fn parse_iter(s: &str) -> ParseIter<'_> {
+    let (_, segments) = split_segments(strip_bom(s), false);
+
+    ParseIter { segments }
+}
+
+/// An iterator over the files in a `txtar` archive, created by [parse_iter].
+pub struct ParseIter<'a> {
+    segments: Segments<'a>,
+}
+
+impl fmt::Debug for ParseIter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParseIter").finish_non_exhaustive()
+    }
+}
+
+impl<'a> Iterator for ParseIter<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.segments
+            .next()
+            .map(|(marker, content)| (marker.name.trim(), content))
+    }
+}
+
+// Parse the given input, offsetting the recorded byte spans of each file by `offset` to account
+// for anything that was stripped from the start of the original input.
+pub(crate) fn parse(s: &str, offset: usize, opts: &ParseOptions) -> Archive {
+    build_archive(s, offset, opts, |segment| finish(segment, opts))
+}
+
+// Parse an owned input, reusing its buffer for the final segment of the input (the content of the
+// last file, or the comment if there are no files) rather than copying it. The buffer is only
+// reused if that segment makes up at least half of the input so that a small final file is never
+// left holding on to a large allocation. Every other segment is copied in the same way as for
+// [parse].
+pub(crate) fn parse_owned(mut s: String, opts: &ParseOptions) -> Archive {
+    let offset = s.len() - strip_bom(&s).len();
+    let len = s.len();
+    let reuse =
+        |start: usize, segment_len: usize| start + segment_len == len && (len - start) * 2 >= len;
+
+    let base = s.as_ptr() as usize;
+    let mut a = build_archive(&s[offset..], offset, opts, |segment| {
+        match reuse(segment.as_ptr() as usize - base, segment.len()) {
+            true => String::new(),
+            false => finish(segment, opts),
+        }
+    });
+
+    let tail = match a.files.len().checked_sub(1) {
+        Some(ix) => a.files.source(ix).map_or(len, |s| s.content.start),
+        None => offset,
+    };
+    if !reuse(tail, len - tail) {
+        return a;
+    }
+
+    s.drain(..tail);
+    if !opts.lossless {
+        push_missing_newline(&mut s);
+    }
+    match a.files.len().checked_sub(1) {
+        Some(ix) => *a.files.content_mut(ix) = s,
+        None => a.comment = s,
+    }
+
+    a
+}
+
+fn finish(segment: &str, opts: &ParseOptions) -> String {
+    // Only the final segment of the input can be missing its trailing newline
+    if opts.lossless {
+        segment.to_string()
+    } else {
+        fix_trailing_newline(segment)
+    }
+}
+
+fn push_missing_newline(s: &mut String) {
+    if !(s.is_empty() || s.ends_with('\n')) {
+        s.push('\n');
+    }
+}
+
+fn build_archive(
+    s: &str,
+    offset: usize,
+    opts: &ParseOptions,
+    finish: impl Fn(&str) -> String,
+) -> Archive {
+    let (comment, entries) = locate_files(s, offset, opts.lenient_markers);
+
+    Archive {
+        comment: finish(comment),
+        files: entries.map(|e| e.into_parsed(opts, &finish)).collect(),
+    }
+}
+
+// A file marker and the content that follows it, along with their position in the input.
+pub(crate) struct Entry<'a> {
+    marker: Marker<'a>,
+    content: &'a str,
+    line: usize,
+    pos: usize,
+}
+
+impl Entry<'_> {
+    // The parsed file along with where it was found in the input.
+    pub(crate) fn into_parsed(
+        self,
+        opts: &ParseOptions,
+        finish: &impl Fn(&str) -> String,
+    ) -> (File, Source) {
+        let Entry {
+            marker,
+            content,
+            line,
+            pos,
+        } = self;
+        let name = if opts.trim_names {
+            marker.name.trim()
+        } else {
+            marker.name
+        };
+        let content_start = pos + marker.line.len();
+        let source = Source {
+            line,
+            marker: pos..content_start,
+            content: content_start..content_start + content.len(),
+            had_trailing_newline: content.is_empty() || content.ends_with('\n'),
+            raw_marker: opts.lossless.then(|| marker.line.to_string()),
+        };
+
+        (File::new(name, finish(content)), source)
+    }
+}
+
+// Split the input into the comment and the location of each file without copying any content.
+pub(crate) fn locate_files(
+    s: &str,
+    offset: usize,
+    lenient: bool,
+) -> (&str, impl Iterator<Item = Entry<'_>>) {
+    let (comment, segments) = split_segments(s, lenient);
+    let mut line = 1 + count_newlines(comment);
+    let mut pos = offset + comment.len();
+
+    let entries = segments.map(move |(marker, content)| {
+        let next_line = line + 1 + count_newlines(content);
+        let next_pos = pos + marker.line.len() + content.len();
+        Entry {
+            marker,
+            content,
+            line: std::mem::replace(&mut line, next_line),
+            pos: std::mem::replace(&mut pos, next_pos),
+        }
+    });
+
+    (comment, entries)
+}
+
+// The original marker line for a file parsed in lossless mode, provided that the file has not
+// since been renamed.
+pub(crate) fn raw_marker<'a>(file: &File, source: Option<&'a Source>) -> Option<&'a str> {
+    let line = source?.raw_marker.as_deref()?;
+    let name = try_parse_marker(line.as_bytes(), true)?.within(line).name;
+
+    (name == file.name || name.trim() == file.name).then_some(line)
+}
+
+fn dedup(files: &mut Files, policy: Duplicates) {
+    let mut seen = HashSet::new();
+    let keep: Vec<bool> = match policy {
+        Duplicates::KeepAll | Duplicates::Error => return,
+        Duplicates::KeepFirst => files.iter().map(|f| seen.insert(&f.name)).collect(),
+        Duplicates::KeepLast => {
+            let mut keep: Vec<bool> = files.iter().rev().map(|f| seen.insert(&f.name)).collect();
+            keep.reverse();
+            keep
+        }
+    };
+
+    let mut keep = keep.into_iter();
+    files.retain(|_| keep.next().unwrap_or(true));
+}
+
+// Scan the file markers in the input without copying any content to check the number and size of
+// the files against the configured limits.
+fn check_limits(s: &str, opts: &ParseOptions) -> Result<(), ParseError> {
+    let Limits {
+        max_files,
+        max_file_bytes,
+        ..
+    } = opts.limits;
+
+    let (comment, segments) = split_segments(s, opts.lenient_markers);
+    let mut line = 1 + count_newlines(comment);
+    let mut n_files = 0;
+
+    for (marker, content) in segments {
+        n_files += 1;
+
+        let kind = match (max_files, max_file_bytes) {
+            (Some(max), _) if n_files > max => ParseErrorKind::TooManyFiles { max },
+            (_, Some(max)) if content.len() > max => ParseErrorKind::FileTooLarge {
+                name: marker.name.trim().to_string(),
+                max,
+            },
+            _ => {
+                line += 1 + count_newlines(content);
+                continue;
+            }
+        };
+
+        return Err(ParseError { line, kind });
+    }
+
+    Ok(())
+}
+
+fn check_lines(s: &str, opts: &ParseOptions) -> Result<(), ParseError> {
+    let mut seen = HashSet::new();
+
+    for (i, line) in s.split('\n').enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let marker = try_parse_marker(line.as_bytes(), opts.lenient_markers);
+        let kind = match marker.map(|m| m.within(line)) {
+            Some(Marker { name, .. }) => {
+                let name = if opts.trim_names { name.trim() } else { name };
+                if opts.strict && name.trim().is_empty() {
+                    ParseErrorKind::EmptyName
+                } else if opts.duplicates == Duplicates::Error && !seen.insert(name) {
+                    ParseErrorKind::DuplicateName(name.to_string())
+                } else {
+                    continue;
+                }
+            }
+            None if opts.strict && looks_like_marker(line.as_bytes()) => {
+                ParseErrorKind::MalformedMarker(line.to_string())
+            }
+            None => continue,
+        };
+
+        return Err(ParseError { line: i + 1, kind });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_parse_errors() {
+        let cases = [
+            (
+                "-- a --\nfoo\n-- foo ---\n",
+                3,
+                ParseErrorKind::MalformedMarker("-- foo ---".to_string()),
+            ),
+            ("-- a --\n--   --\n", 2, ParseErrorKind::EmptyName),
+            (
+                "c\n-- a --\n-- b --\n--  a --",
+                4,
+                ParseErrorKind::DuplicateName("a".to_string()),
+            ),
+            (
+                "-- a --\n--b --\n",
+                2,
+                ParseErrorKind::MalformedMarker("--b --".to_string()),
+            ),
+        ];
+
+        for (s, line, kind) in cases {
+            assert_eq!(
+                Archive::parse_strict(s),
+                Err(ParseError { line, kind }),
+                "{s:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn strict_parse_ignores_dash_only_lines() {
+        let s = "-- a --\n----\n-- --\n-----  -----\n";
+
+        assert_eq!(Archive::parse_strict(s), Ok(Archive::from(s)));
+    }
+
+    #[test]
+    fn crlf_markers_are_recognised() {
+        let s = "comment\r\n-- a --\r\nfoo\r\n-- b --\r\nbar\r\n-- c --\r";
+        let expected = Archive {
+            comment: "comment\r\n".to_string(),
+            files: vec![
+                File::new("a", "foo\r\n"),
+                File::new("b", "bar\r\n"),
+                File::new("c", ""),
+            ]
+            .into(),
+        };
+
+        assert_eq!(Archive::from(s), expected);
+        assert_eq!(Archive::parse_strict(s), Ok(expected));
+    }
+
+    #[test]
+    fn leading_bom_is_stripped_by_default() {
+        let with_comment = "\u{feff}comment\n-- a --\nfoo\n";
+        let with_marker = "\u{feff}-- a --\nfoo\n";
+
+        assert_eq!(Archive::from(with_comment).comment(), "comment\n");
+        assert_eq!(Archive::from(with_marker), Archive::from("-- a --\nfoo\n"));
+
+        let a = Archive::from_str_with(with_marker, ParseOptions::new().strip_bom(false)).unwrap();
+        assert_eq!(a.comment(), with_marker);
+        assert!(a.is_empty());
+        assert_eq!(a.to_string(), "-- a --\nfoo\n");
+    }
+
+    #[test]
+    fn lossless_round_trip() {
+        let cases = [
+            "",
+            "comment only",
+            "\u{feff}comment\r\n--  a  --\r\nfoo\r\n-- b --\nbar",
+            "-- a --\n-- b --",
+            "-- a --\nfoo\n-- b --\n",
+            "c\n-- a --\n\n\n",
+        ];
+
+        for s in cases {
+            let a = Archive::from_str_with(s, ParseOptions::new().lossless(true)).unwrap();
+            assert_eq!(a.to_string_exact(), s, "{s:?}");
+        }
+    }
+
+    #[test]
+    fn exact_output_uses_generated_markers_for_renamed_files() {
+        let s = "--  a  --\nfoo\n--  b  --\nbar";
+        let mut a = Archive::from_str_with(s, ParseOptions::new().lossless(true)).unwrap();
+        a.rename("b", "c");
+        a.add_file(("d", "no newline"));
+
+        assert_eq!(
+            a.to_string_exact(),
+            "--  a  --\nfoo\n-- c --\nbar\n-- d --\nno newline"
+        );
+    }
+
+    #[test]
+    fn line_numbers_are_recorded() {
+        let s = "c1\nc2\n-- a --\n-- b --\n1\n2\n3\n-- c --\r\nx\r\n-- d --";
+        let a = Archive::from(s);
+        let lines: Vec<_> = (0..a.len())
+            .map(|i| (a.marker_line(i), a.content_start_line(i)))
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                (Some(3), Some(4)),
+                (Some(4), Some(5)),
+                (Some(8), Some(9)),
+                (Some(10), Some(11)),
+            ]
+        );
+    }
+
+    #[test]
+    fn byte_spans_are_relative_to_the_original_input() {
+        let s = "\u{feff}c\n-- a --\r\nfoo\r\n-- b --";
+        let a = Archive::from(s);
+
+        assert_eq!(&s[a.marker_span(0).unwrap()], "-- a --\r\n");
+        assert_eq!(&s[a.content_span(0).unwrap()], "foo\r\n");
+        assert_eq!(&s[a.marker_span(1).unwrap()], "-- b --");
+        assert_eq!(a.content_span(1), Some(s.len()..s.len()));
+    }
+
+    #[test]
+    fn lenient_markers() {
+        let s = "c\n--- a ---\n1\n-- b--\n2\n--c --\n3\n----\n-- d --\n4\n";
+        let mut opts = ParseOptions::new();
+        opts.lenient_markers(true);
+        let a = Archive::from_str_with(s, &opts).unwrap();
+
+        let expected: Archive = [("a", "1\n"), ("b", "2\n"), ("c", "3\n----\n"), ("d", "4\n")]
+            .into_iter()
+            .collect();
+        assert_eq!(a, expected.with_comment("c\n"));
+
+        let a = Archive::from_str_with(s, opts.lossless(true)).unwrap();
+        assert_eq!(a.to_string_exact(), s);
+    }
+
+    #[test]
+    fn file_size_limit() {
+        let s = "-- a --\nfoo\n-- b --\nlonger content\n";
+        let err = Archive::from_str_limited(s, Limits::new().max_file_bytes(8));
+
+        assert_eq!(
+            err,
+            Err(ParseError {
+                line: 3,
+                kind: ParseErrorKind::FileTooLarge {
+                    name: "b".to_string(),
+                    max: 8
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn duplicate_policies() {
+        let s = "-- a --\n1\n-- b --\n2\n-- a --\n3\n";
+        let cases = [
+            (
+                Duplicates::KeepAll,
+                vec![("a", "1\n"), ("b", "2\n"), ("a", "3\n")],
+            ),
+            (Duplicates::KeepFirst, vec![("a", "1\n"), ("b", "2\n")]),
+            (Duplicates::KeepLast, vec![("b", "2\n"), ("a", "3\n")]),
+        ];
+
+        for (policy, files) in cases {
+            let a = Archive::from_str_with(s, ParseOptions::new().duplicates(policy)).unwrap();
+            assert_eq!(a, files.into_iter().collect(), "{policy:?}");
+        }
+
+        let err = Archive::from_str_with(s, ParseOptions::new().duplicates(Duplicates::Error));
+        assert_eq!(
+            err,
+            Err(ParseError {
+                line: 5,
+                kind: ParseErrorKind::DuplicateName("a".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn markers_are_found_throughout_large_inputs() {
+        let s: String = (0..1000).map(|i| format!("-- {i} --\n{i}\n")).collect();
+        let a = Archive::from(format!("comment\n-- x--\n{s}").as_str());
+
+        assert_eq!(a.comment(), "comment\n-- x--\n");
+        assert_eq!(a.len(), 1000);
+        assert!(a.iter().enumerate().all(|(i, f)| f.name == i.to_string()
+            && f.content == format!("{i}\n")
+            && a.marker_line(i) == Some(3 + 2 * i)));
+    }
+
+    #[test]
+    fn owned_parse_matches_borrowed_parse() {
+        let inputs = [
+            "",
+            "\u{feff}comment",
+            "\u{feff}comment\n-- a --\nfoo\n--  b  --\r\n\n-- c --\nbar",
+            "-- a --\n-- b --",
+        ];
+
+        for s in inputs {
+            let owned = parse_owned(s.to_string(), &ParseOptions::default());
+            let borrowed = Archive::from(s);
+
+            assert_eq!(owned, borrowed, "{s:?}");
+            assert_eq!(owned.files.sources(), borrowed.files.sources(), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn owned_parse_reuses_the_input_for_a_large_final_file() {
+        let large = format!("comment\n-- a --\nfoo\n-- b --\n{}", "bar\n".repeat(100));
+        let ptr = large.as_ptr();
+        let a = parse_owned(large, &ParseOptions::default());
+
+        let small = format!("-- a --\n{}-- b --\nbar\n", "foo\n".repeat(100));
+        let small_ptr = small.as_ptr();
+        let b = parse_owned(small, &ParseOptions::default());
+
+        assert_eq!(a["b"].content.as_ptr(), ptr);
+        assert_eq!(a["b"].content, "bar\n".repeat(100));
+        assert_ne!(b["b"].content.as_ptr(), small_ptr);
+    }
+}
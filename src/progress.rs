@@ -0,0 +1,32 @@
+//! Reporting the progress of packing and extracting archives.
+
+/// The progress of packing or extracting a single file, passed to the callbacks registered with
+/// [PackOptions::on_progress][crate::PackOptions::on_progress] and
+/// [ExtractOptions::on_progress][crate::ExtractOptions::on_progress].
+///
+/// ## Example
+/// ```no_run
+/// use simple_txtar::{Archive, ExtractOptions};
+///
+/// let a = Archive::from_file("corpus.txtar").unwrap();
+/// a.materialize_with(
+///     "testdata",
+///     ExtractOptions::new().on_progress(|p| {
+///         eprintln!("[{}/{}] {} ({} bytes)", p.index + 1, p.total, p.name, p.bytes)
+///     }),
+/// )
+/// .unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress<'a> {
+    /// The name of the file within the archive
+    pub name: &'a str,
+    /// The number of files that were completed before this one
+    pub index: usize,
+    /// The total number of files that will be processed
+    pub total: usize,
+    /// The size of the file's content in bytes
+    pub bytes: u64,
+}
+
+pub(crate) type ProgressFn = dyn Fn(Progress<'_>) + Send + Sync;
@@ -0,0 +1,235 @@
+//! A low-level pull parser emitting events for each part of a `txtar` archive.
+use crate::scan::{split_segments, strip_bom, Segments};
+use std::{fmt, ops::ControlFlow};
+
+/// An event emitted by a [Parser].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event<'a> {
+    /// The comment at the start of the archive, which is only emitted if it is not empty
+    Comment(&'a str),
+    /// The start of a file with the given name
+    FileStart(&'a str),
+    /// A single line of content from the current file, including its line ending if present
+    ContentChunk(&'a str),
+    /// The end of the current file
+    FileEnd,
+}
+
+/// A pull parser that emits an [Event] for each part of a `txtar` archive as it is advanced.
+///
+/// This uses the same tokenizer as the rest of the crate, so the events emitted always agree with
+/// how the archive would be parsed into an [Archive][crate::Archive], but no content is copied
+/// or allocated. Content is returned exactly as it appears in the input, so the final line of the
+/// archive may be missing its trailing newline.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::{Event, Parser};
+///
+/// let events: Vec<_> = Parser::new("comment\n-- a.txt --\nfoo\nbar\n-- b.txt --\n").collect();
+///
+/// assert_eq!(
+///     events,
+///     vec![
+///         Event::Comment("comment\n"),
+///         Event::FileStart("a.txt"),
+///         Event::ContentChunk("foo\n"),
+///         Event::ContentChunk("bar\n"),
+///         Event::FileEnd,
+///         Event::FileStart("b.txt"),
+///         Event::FileEnd,
+///     ]
+/// );
+/// ```
+pub struct Parser<'a> {
+    segments: Segments<'a>,
+    comment: Option<&'a str>,
+    content: Option<&'a str>,
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Create a new [Parser] for the given input.
+    pub fn new(s: &'a str) -> Self {
+        let body = strip_bom(s);
+        let (comment, segments) = split_segments(body, false);
+
+        Self {
+            segments,
+            comment: Some(comment),
+            content: None,
+            offset: s.len() - body.len(),
+        }
+    }
+
+    /// The number of bytes of the input that have been consumed by the events emitted so far.
+    ///
+    /// Reading this before and after advancing the parser gives the byte range within the input
+    /// that the emitted event covers, including the full marker line for [Event::FileStart].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Parser;
+    ///
+    /// let s = "-- a.txt --\nfoo\n";
+    /// let mut p = Parser::new(s);
+    ///
+    /// p.next();
+    /// assert_eq!(&s[..p.offset()], "-- a.txt --\n");
+    /// ```
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'a> Iterator for Parser<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(comment) = self.comment.take() {
+            if !comment.is_empty() {
+                self.offset += comment.len();
+                return Some(Event::Comment(comment));
+            }
+        }
+
+        if let Some(content) = self.content.as_mut() {
+            if content.is_empty() {
+                self.content = None;
+                return Some(Event::FileEnd);
+            }
+
+            let end = content.find('\n').map_or(content.len(), |i| i + 1);
+            let (chunk, rest) = content.split_at(end);
+            *content = rest;
+            self.offset += chunk.len();
+
+            return Some(Event::ContentChunk(chunk));
+        }
+
+        let (marker, content) = self.segments.next()?;
+        self.content = Some(content);
+        self.offset += marker.line.len();
+
+        Some(Event::FileStart(marker.name.trim()))
+    }
+}
+
+impl fmt::Debug for Parser<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Parser")
+            .field("offset", &self.offset)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Callbacks for each part of a `txtar` archive, used with [parse_with].
+///
+/// All methods have default implementations that ignore their input, so implementors only need
+/// to provide the callbacks they are interested in.
+pub trait Visitor<'a> {
+    /// Called with the comment at the start of the archive, even if it is empty.
+    fn visit_comment(&mut self, comment: &'a str) {
+        let _ = comment;
+    }
+
+    /// Called with the name and content of each file in the order they appear in the archive.
+    ///
+    /// Returning [ControlFlow::Break] stops parsing without visiting any further files.
+    fn visit_file(&mut self, name: &'a str, content: &'a str) -> ControlFlow<()> {
+        let _ = (name, content);
+        ControlFlow::Continue(())
+    }
+}
+
+/// Parse a `txtar` archive, passing each part of it to the given [Visitor] rather than building
+/// an [Archive][crate::Archive].
+///
+/// The comment and the content of each file are borrowed from the input exactly as they appear,
+/// so a visitor that only keeps some of the files never allocates the others. As with
+/// [parse_iter][crate::parse_iter], the content of the final file may be missing its trailing
+/// newline.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::{parse_with, Archive, Visitor};
+/// use std::ops::ControlFlow;
+///
+/// struct SmallFiles(Archive);
+///
+/// impl<'a> Visitor<'a> for SmallFiles {
+///     fn visit_file(&mut self, name: &'a str, content: &'a str) -> ControlFlow<()> {
+///         if content.len() <= 8 {
+///             self.0.add_file((name, content));
+///         }
+///         ControlFlow::Continue(())
+///     }
+/// }
+///
+/// let mut v = SmallFiles(Archive::default());
+/// parse_with("-- a --\nsmall\n-- b --\nmuch too large\n", &mut v);
+///
+/// assert_eq!(v.0.to_string(), "-- a --\nsmall\n");
+/// ```
+pub fn parse_with<'a>(s: &'a str, visitor: &mut impl Visitor<'a>) {
+    let (comment, segments) = split_segments(strip_bom(s), false);
+    visitor.visit_comment(comment);
+
+    for (marker, content) in segments {
+        if visitor.visit_file(marker.name.trim(), content).is_break() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_cover_the_input() {
+        let s = "\u{feff}c\n-- a --\r\nfoo\r\n--  b  --\nbar";
+        let mut p = Parser::new(s);
+        let mut spans = Vec::new();
+        let mut start = p.offset();
+
+        while let Some(event) = p.next() {
+            spans.push((event, &s[start..p.offset()]));
+            start = p.offset();
+        }
+
+        assert_eq!(start, s.len());
+        assert_eq!(
+            spans,
+            vec![
+                (Event::Comment("c\n"), "c\n"),
+                (Event::FileStart("a"), "-- a --\r\n"),
+                (Event::ContentChunk("foo\r\n"), "foo\r\n"),
+                (Event::FileEnd, ""),
+                (Event::FileStart("b"), "--  b  --\n"),
+                (Event::ContentChunk("bar"), "bar"),
+                (Event::FileEnd, ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn visitors_can_stop_early() {
+        struct Names(Vec<String>);
+
+        impl Visitor<'_> for Names {
+            fn visit_file(&mut self, name: &str, _: &str) -> ControlFlow<()> {
+                self.0.push(name.to_string());
+                match name {
+                    "b" => ControlFlow::Break(()),
+                    _ => ControlFlow::Continue(()),
+                }
+            }
+        }
+
+        let mut v = Names(Vec::new());
+        parse_with("-- a --\n-- b --\n-- c --\n", &mut v);
+
+        assert_eq!(v.0, vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,72 @@
+//! Quoting of file content that would otherwise be parsed as file markers.
+use crate::{scan::is_marker, UnquoteError};
+
+/// Check whether the given file content contains lines that would be parsed as file markers if it
+/// were stored in a `txtar` archive without first being quoted using [quote].
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::needs_quote;
+///
+/// assert!(needs_quote("some text\n-- not a file --\n"));
+/// assert!(!needs_quote("some text\n-- not a file--\n"));
+/// ```
+pub fn needs_quote(content: &str) -> bool {
+    content.lines().any(is_marker)
+}
+
+/// Quote file content so that it can be safely stored within a `txtar` archive, even if it
+/// contains lines that look like file markers.
+///
+/// Every line is prefixed with `>`, and a final newline is added if it is missing in the same way
+/// as it would be when parsing. The original content can be recovered using [unquote].
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::{quote, unquote, Archive};
+///
+/// let content = "nested\n-- a.txt --\nfoo\n";
+/// let quoted = quote(content);
+/// assert_eq!(quoted, ">nested\n>-- a.txt --\n>foo\n");
+///
+/// let a = Archive::builder().file(("outer.txt", quoted)).build();
+/// let a = Archive::from(a.to_string());
+///
+/// assert_eq!(a.len(), 1);
+/// assert_eq!(unquote(&a["outer.txt"].content).unwrap(), content);
+/// ```
+pub fn quote(content: &str) -> String {
+    let mut quoted = String::with_capacity(content.len() + content.lines().count() + 1);
+    for line in content.split_inclusive('\n') {
+        quoted.push('>');
+        quoted.push_str(line);
+    }
+    if !(quoted.is_empty() || quoted.ends_with('\n')) {
+        quoted.push('\n');
+    }
+
+    quoted
+}
+
+/// Recover file content that was quoted using [quote], removing the `>` prefix from each line.
+///
+/// Returns an error if any line is missing its `>` prefix.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::unquote;
+///
+/// assert_eq!(unquote(">-- a.txt --\n>foo\n").unwrap(), "-- a.txt --\nfoo\n");
+/// assert_eq!(unquote(">foo\nbar\n").unwrap_err().line, 2);
+/// ```
+pub fn unquote(content: &str) -> Result<String, UnquoteError> {
+    let mut unquoted = String::with_capacity(content.len());
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        match line.strip_prefix('>') {
+            Some(line) => unquoted.push_str(line),
+            None => return Err(UnquoteError { line: i + 1 }),
+        }
+    }
+
+    Ok(unquoted)
+}
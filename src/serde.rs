@@ -0,0 +1,224 @@
+//! [serde] support for [Archive] and [File].
+//!
+//! An [Archive] is represented as a struct with a `comment` and a list of `files`, each of which
+//! is a struct with a `name` and `content`. Both fields of an archive default to being empty when
+//! deserializing. Details of the original input that are retained by parsing, such as the
+//! positions of files, are not serialized.
+//!
+//! The [map] and [list] modules provide alternative representations for use with
+//! `#[serde(with = "...")]`.
+use crate::{Archive, File};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+
+#[derive(Serialize)]
+struct ArchiveRepr<'a> {
+    comment: &'a str,
+    files: &'a [File],
+}
+
+#[derive(Deserialize)]
+struct OwnedArchiveRepr {
+    #[serde(default)]
+    comment: String,
+    #[serde(default)]
+    files: Vec<File>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileRepr<'a> {
+    #[serde(borrow)]
+    name: Cow<'a, str>,
+    #[serde(borrow)]
+    content: Cow<'a, str>,
+}
+
+/// ## Example
+/// ```rust
+/// use simple_txtar::Archive;
+///
+/// let a = Archive::from("comment\n-- a.txt --\nfoo\n");
+/// let json = serde_json::to_string(&a).unwrap();
+///
+/// assert_eq!(
+///     json,
+///     r#"{"comment":"comment\n","files":[{"name":"a.txt","content":"foo\n"}]}"#
+/// );
+/// ```
+impl Serialize for Archive {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ArchiveRepr {
+            comment: self.comment(),
+            files: &self.files,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Archive {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let OwnedArchiveRepr { comment, files } = OwnedArchiveRepr::deserialize(deserializer)?;
+
+        Ok(Self {
+            comment,
+            files: files.into(),
+        })
+    }
+}
+
+impl Serialize for File {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        FileRepr {
+            name: Cow::Borrowed(&self.name),
+            content: Cow::Borrowed(&self.content),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for File {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let FileRepr { name, content } = FileRepr::deserialize(deserializer)?;
+
+        Ok(File::new(name, content))
+    }
+}
+
+/// Represent an [Archive] as a map from file names to their content, for use with
+/// `#[serde(with = "simple_txtar::serde::map")]`.
+///
+/// This is often the most convenient shape for hand written JSON or YAML. Files are serialized
+/// in order and the order of the entries is preserved when deserializing. The comment of the
+/// archive is not included and serializing an archive that contains multiple files with the same
+/// name is an error.
+///
+/// ## Example
+/// ```rust
+/// use serde::{Deserialize, Serialize};
+/// use simple_txtar::Archive;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct TestCase {
+///     name: String,
+///     #[serde(with = "simple_txtar::serde::map")]
+///     files: Archive,
+/// }
+///
+/// let json = r#"{"name":"basic","files":{"b.txt":"foo\n","a.txt":"bar\n"}}"#;
+/// let case: TestCase = serde_json::from_str(json).unwrap();
+///
+/// assert_eq!(case.files.to_string(), "-- b.txt --\nfoo\n-- a.txt --\nbar\n");
+/// assert_eq!(serde_json::to_string(&case).unwrap(), json);
+/// ```
+pub mod map {
+    use crate::{Archive, File};
+    use serde::{
+        de::{MapAccess, Visitor},
+        ser::{Error, SerializeMap},
+        Deserializer, Serializer,
+    };
+    use std::{collections::HashSet, fmt};
+
+    /// Serialize an [Archive] as a map from file names to their content.
+    pub fn serialize<S: Serializer>(a: &Archive, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seen = HashSet::with_capacity(a.len());
+        let mut map = serializer.serialize_map(Some(a.len()))?;
+        for f in a.iter() {
+            if !seen.insert(f.name.as_str()) {
+                return Err(S::Error::custom(format!("duplicate file name: {}", f.name)));
+            }
+            map.serialize_entry(&f.name, &f.content)?;
+        }
+
+        map.end()
+    }
+
+    /// Deserialize an [Archive] from a map of file names to their content.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Archive, D::Error> {
+        deserializer.deserialize_map(FilesVisitor)
+    }
+
+    struct FilesVisitor;
+
+    impl<'de> Visitor<'de> for FilesVisitor {
+        type Value = Archive;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a map of file names to their content")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut files = Vec::with_capacity(map.size_hint().unwrap_or_default());
+            while let Some((name, content)) = map.next_entry::<String, String>()? {
+                files.push(File::new(name, content));
+            }
+
+            Ok(Archive::from_files(files))
+        }
+    }
+}
+
+/// Represent an [Archive] as a list of its files, for use with
+/// `#[serde(with = "simple_txtar::serde::list")]`.
+///
+/// Each file is represented in the same way as the default representation, but the comment of
+/// the archive is not included.
+///
+/// ## Example
+/// ```rust
+/// use serde::{Deserialize, Serialize};
+/// use simple_txtar::Archive;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Snapshot {
+///     #[serde(with = "simple_txtar::serde::list")]
+///     files: Archive,
+/// }
+///
+/// let json = r#"{"files":[{"name":"a.txt","content":"foo\n"}]}"#;
+/// let s: Snapshot = serde_json::from_str(json).unwrap();
+///
+/// assert_eq!(s.files.to_string(), "-- a.txt --\nfoo\n");
+/// assert_eq!(serde_json::to_string(&s).unwrap(), json);
+/// ```
+pub mod list {
+    use crate::{Archive, File};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// Serialize an [Archive] as a list of its files.
+    pub fn serialize<S: Serializer>(a: &Archive, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(a.iter())
+    }
+
+    /// Deserialize an [Archive] from a list of files.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Archive, D::Error> {
+        Ok(Archive::from_files(Vec::<File>::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_json() {
+        let a = Archive::from("\u{feff}comment\n-- a --\nfoo\n-- b --\n-- a --\nbar");
+        let json = serde_json::to_string(&a).unwrap();
+        let b: Archive = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(a, b);
+        assert_eq!(
+            serde_json::from_str::<Archive>("{}").unwrap(),
+            Archive::default()
+        );
+    }
+
+    #[test]
+    fn map_rejects_duplicate_names() {
+        let a = Archive::from("-- a --\nfoo\n-- a --\nbar\n");
+        let mut buf = Vec::new();
+        let res = map::serialize(&a, &mut serde_json::Serializer::new(&mut buf));
+
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! An immutable archive that can be cheaply cloned and shared between threads.
+use crate::{fmt_with_trailing_newline, Archive, File, BOM, MARKER, MARKER_END};
+use std::{collections::HashSet, fmt, ops::Index, slice::Iter, sync::Arc};
+
+/// An immutable `txtar` archive backed by reference counted storage.
+///
+/// Cloning an [Archive] copies the content of every file, whereas cloning a [SharedArchive] only
+/// increments a reference count. This makes it well suited to loading a set of fixtures once and
+/// handing them out to many tests or threads. Convert back into an [Archive] when the contents
+/// need to be modified.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::{Archive, SharedArchive};
+/// use std::thread;
+///
+/// let a = SharedArchive::from(Archive::from("-- a.txt --\nfoo\n"));
+///
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let a = a.clone();
+///         thread::spawn(move || a["a.txt"].content.len())
+///     })
+///     .collect();
+///
+/// for h in handles {
+///     assert_eq!(h.join().unwrap(), 4);
+/// }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SharedArchive {
+    comment: Arc<str>,
+    files: Arc<[SharedFile]>,
+}
+
+impl SharedArchive {
+    /// The optional comment at the top of the `txtar` archive.
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// Attempt to get a [SharedFile] from the archive by name, returning the first match.
+    pub fn get(&self, filename: &str) -> Option<&SharedFile> {
+        self.files.iter().find(|f| &*f.name == filename)
+    }
+
+    /// Iterate over the [SharedFile]s contained in this archive in order.
+    pub fn iter(&self) -> Iter<'_, SharedFile> {
+        self.files.iter()
+    }
+
+    /// The number of files in the archive.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether or not the archive contains any files.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Copy the contents of this archive into a new [Archive] that can be modified.
+    pub fn to_archive(&self) -> Archive {
+        Archive {
+            comment: self.comment.to_string(),
+            files: self.files.iter().map(File::from).collect(),
+        }
+    }
+
+    /// Store identical file contents only once, with every file that has the same content
+    /// sharing a single allocation.
+    ///
+    /// Returns a summary of the memory saved by sharing contents. Files whose contents are
+    /// already shared, such as those in a clone of this archive, are left as they are.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::SharedArchive;
+    /// use std::sync::Arc;
+    ///
+    /// let mut a = SharedArchive::from("-- a --\nboilerplate\n-- b --\nboilerplate\n-- c --\n");
+    /// let stats = a.dedup_contents();
+    ///
+    /// assert!(Arc::ptr_eq(&a[0].content, &a[1].content));
+    /// assert_eq!(stats.unique_contents, 2);
+    /// assert_eq!(stats.bytes_saved, "boilerplate\n".len());
+    /// ```
+    pub fn dedup_contents(&mut self) -> DedupStats {
+        let mut seen: HashSet<Arc<str>> = HashSet::new();
+        let mut bytes_saved = 0;
+
+        let files: Arc<[SharedFile]> = self
+            .files
+            .iter()
+            .map(|f| {
+                let content = match seen.get(&f.content) {
+                    Some(existing) => {
+                        if !Arc::ptr_eq(existing, &f.content) {
+                            bytes_saved += f.content.len();
+                        }
+                        existing.clone()
+                    }
+                    None => {
+                        seen.insert(f.content.clone());
+                        f.content.clone()
+                    }
+                };
+
+                SharedFile {
+                    name: f.name.clone(),
+                    content,
+                }
+            })
+            .collect();
+        self.files = files;
+
+        DedupStats {
+            files: self.files.len(),
+            unique_contents: seen.len(),
+            bytes_saved,
+        }
+    }
+
+    /// Convert an [Archive] into a [SharedArchive], interning the file names using the given
+    /// [Interner][crate::Interner] so that names are shared with any other archives using the
+    /// same interner.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::{Archive, Interner, SharedArchive};
+    /// use std::sync::Arc;
+    ///
+    /// let mut names = Interner::new();
+    /// let a = SharedArchive::from_archive_interned(&Archive::from("-- a --\n1\n"), &mut names);
+    /// let b = SharedArchive::from_archive_interned(&Archive::from("-- a --\n2\n"), &mut names);
+    ///
+    /// assert!(Arc::ptr_eq(&a[0].name, &b[0].name));
+    /// ```
+    #[cfg(feature = "intern")]
+    pub fn from_archive_interned(a: &Archive, names: &mut crate::Interner) -> Self {
+        Self {
+            comment: Arc::from(a.comment()),
+            files: a
+                .iter()
+                .map(|f| SharedFile {
+                    name: names.intern(&f.name),
+                    content: Arc::from(f.content.as_str()),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&Archive> for SharedArchive {
+    fn from(a: &Archive) -> Self {
+        Self {
+            comment: Arc::from(a.comment()),
+            files: a.iter().map(SharedFile::from).collect(),
+        }
+    }
+}
+
+impl From<Archive> for SharedArchive {
+    fn from(a: Archive) -> Self {
+        Self {
+            comment: Arc::from(a.comment),
+            files: a.files.into_iter().map(SharedFile::from).collect(),
+        }
+    }
+}
+
+impl From<&str> for SharedArchive {
+    fn from(s: &str) -> Self {
+        Self::from(Archive::from(s))
+    }
+}
+
+impl From<SharedArchive> for Archive {
+    fn from(a: SharedArchive) -> Self {
+        a.to_archive()
+    }
+}
+
+impl Index<usize> for SharedArchive {
+    type Output = SharedFile;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.files[index]
+    }
+}
+
+impl Index<&str> for SharedArchive {
+    type Output = SharedFile;
+
+    fn index(&self, filename: &str) -> &Self::Output {
+        self.get(filename).unwrap()
+    }
+}
+
+impl<'a> IntoIterator for &'a SharedArchive {
+    type Item = &'a SharedFile;
+    type IntoIter = Iter<'a, SharedFile>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.iter()
+    }
+}
+
+impl fmt::Display for SharedArchive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let comment = self.comment.strip_prefix(BOM).unwrap_or(&self.comment);
+        fmt_with_trailing_newline(f, comment)?;
+        for file in self.files.iter() {
+            writeln!(f, "{MARKER}{}{MARKER_END}", file.name)?;
+            fmt_with_trailing_newline(f, &file.content)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A summary of the memory saved by [SharedArchive::dedup_contents].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DedupStats {
+    /// The number of files in the archive
+    pub files: usize,
+    /// The number of distinct file contents in the archive
+    pub unique_contents: usize,
+    /// The number of bytes of content that are no longer stored separately
+    pub bytes_saved: usize,
+}
+
+/// A single file within a [SharedArchive].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SharedFile {
+    /// The name of the file
+    pub name: Arc<str>,
+    /// The content of the file
+    pub content: Arc<str>,
+}
+
+impl From<&File> for SharedFile {
+    fn from(f: &File) -> Self {
+        Self {
+            name: Arc::from(f.name.as_str()),
+            content: Arc::from(f.content.as_str()),
+        }
+    }
+}
+
+impl From<File> for SharedFile {
+    fn from(f: File) -> Self {
+        Self {
+            name: Arc::from(f.name),
+            content: Arc::from(f.content),
+        }
+    }
+}
+
+impl From<&SharedFile> for File {
+    fn from(f: &SharedFile) -> Self {
+        File::new(&*f.name, &*f.content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_storage() {
+        let s = "comment\n-- a --\nfoo\n-- b --\nbar";
+        let a = SharedArchive::from(s);
+        let b = a.clone();
+
+        assert!(Arc::ptr_eq(&a.files, &b.files));
+        assert_eq!(b.to_archive(), Archive::from(s));
+        assert_eq!(b.to_string(), Archive::from(s).to_string());
+    }
+}
@@ -0,0 +1,129 @@
+//! Generating shell scripts that recreate the files in an [Archive].
+use crate::{extract::safe_relative_path, Archive, Error};
+use std::collections::HashSet;
+
+const DELIMITER: &str = "TXTAR_EOF";
+
+impl Archive {
+    /// Render this archive as a POSIX shell script that recreates its files beneath the current
+    /// directory when run.
+    ///
+    /// The comment is included at the top of the script as shell comments. Parent directories are
+    /// created as needed and the content of each file is written using a quoted heredoc, so that
+    /// it is written exactly as-is without any expansion. Content without a trailing newline is
+    /// written using `printf` instead so that none is added. As with [Archive::materialize], this
+    /// will error if any file name is absolute or contains a `..` component.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("Setup for a test\n-- dir/a.txt --\n$HOME\n");
+    /// let script = a.to_shell_script().unwrap();
+    ///
+    /// assert_eq!(
+    ///     script.lines().collect::<Vec<_>>(),
+    ///     [
+    ///         "#!/bin/sh",
+    ///         "# Setup for a test",
+    ///         "set -e",
+    ///         "mkdir -p -- 'dir'",
+    ///         "cat > 'dir/a.txt' <<'TXTAR_EOF'",
+    ///         "$HOME",
+    ///         "TXTAR_EOF",
+    ///     ]
+    /// );
+    /// ```
+    pub fn to_shell_script(&self) -> Result<String, Error> {
+        let mut s = String::from("#!/bin/sh\n");
+        for line in self.comment_without_bom().lines() {
+            match line.is_empty() {
+                true => s.push_str("#\n"),
+                false => s.push_str(&format!("# {line}\n")),
+            }
+        }
+        s.push_str("set -e\n");
+
+        let mut dirs = HashSet::new();
+        for f in self.files.iter() {
+            safe_relative_path(&f.name)?;
+            if let Some((dir, _)) = f.name.rsplit_once('/') {
+                if dirs.insert(dir) {
+                    s.push_str(&format!("mkdir -p -- {}\n", quote(dir)));
+                }
+            }
+
+            let path = quote(&f.name);
+            if f.content.is_empty() {
+                s.push_str(&format!(": > {path}\n"));
+            } else if f.content.ends_with('\n') {
+                let delimiter = unique_delimiter(&f.content);
+                s.push_str(&format!(
+                    "cat > {path} <<'{delimiter}'\n{}{delimiter}\n",
+                    f.content
+                ));
+            } else {
+                s.push_str(&format!("printf '%s' {} > {path}\n", quote(&f.content)));
+            }
+        }
+
+        Ok(s)
+    }
+}
+
+/// Wrap `s` in single quotes, escaping any single quotes that it contains.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// A heredoc delimiter that does not appear as a line in `content`.
+fn unique_delimiter(content: &str) -> String {
+    let lines: HashSet<&str> = content.lines().collect();
+    let mut delimiter = DELIMITER.to_string();
+    let mut n = 0;
+    while lines.contains(delimiter.as_str()) {
+        n += 1;
+        delimiter = format!("{DELIMITER}_{n}");
+    }
+
+    delimiter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn script_recreates_files() {
+        use crate::{test_dir::TestDir, File};
+        use std::{fs, process::Command};
+
+        let dir = TestDir::new("shell");
+        let mut a = Archive::from(
+            "comment\n\nmore\n-- a/it's.txt --\n$(echo no) `x` \\n\nTXTAR_EOF\n-- a/b/empty --\n",
+        );
+        a.files.push(File::new("-c", "no newline"));
+        fs::write(dir.join("setup.sh"), a.to_shell_script().unwrap()).unwrap();
+
+        let status = Command::new("sh")
+            .arg("setup.sh")
+            .current_dir(&dir)
+            .status();
+        fs::remove_file(dir.join("setup.sh")).unwrap();
+        let res = Archive::from_dir(&dir);
+
+        let sorted = |a: Archive| {
+            let mut files: Vec<File> = a.into_iter().collect();
+            files.sort_by(|a, b| a.name.cmp(&b.name));
+            files
+        };
+        assert!(status.unwrap().success());
+        assert_eq!(sorted(res.unwrap()), sorted(a));
+    }
+
+    #[test]
+    fn unsafe_names_are_rejected() {
+        assert!(Archive::from("-- ../a --\n").to_shell_script().is_err());
+    }
+}
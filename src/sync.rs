@@ -0,0 +1,213 @@
+//! Keeping an [Archive] in step with a directory on disk.
+use crate::{Archive, ByteArchive, File};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::Path,
+};
+
+/// The changes made to an [Archive] by [Archive::sync_from_dir].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SyncReport {
+    /// The names of files that were added to the end of the archive
+    pub added: Vec<String>,
+    /// The names of files whose content was updated
+    pub modified: Vec<String>,
+    /// The names of files that were removed from the archive
+    pub removed: Vec<String>,
+}
+
+impl SyncReport {
+    /// Whether or not the archive was left unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// The differences between an [Archive] and a directory on disk, as found by
+/// [Archive::diff_dir].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DirDiff {
+    /// Files in the archive that do not exist in the directory
+    pub missing_on_disk: Vec<String>,
+    /// Files in the directory that are not in the archive
+    pub missing_in_archive: Vec<String>,
+    /// Files that exist in both but with different content
+    pub content_differs: Vec<String>,
+}
+
+impl DirDiff {
+    /// Whether or not the archive and directory have the same contents.
+    pub fn is_empty(&self) -> bool {
+        self.missing_on_disk.is_empty()
+            && self.missing_in_archive.is_empty()
+            && self.content_differs.is_empty()
+    }
+}
+
+impl Archive {
+    /// Update this archive to match the text files under the given directory, changing only the
+    /// files that differ.
+    ///
+    /// Files whose content on disk has changed are updated in place, files that no longer exist
+    /// on disk are removed and new files are added to the end of the archive in the order used
+    /// by [Archive::from_dir]. The comment and the order of existing files are preserved so that
+    /// regenerating a golden archive produces a minimal diff. This will error under the same
+    /// conditions as [Archive::from_dir], in which case the archive is left unchanged.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let mut a = Archive::from_file("testdata/golden.txtar").unwrap();
+    /// let report = a.sync_from_dir("target/output").unwrap();
+    ///
+    /// if !report.is_empty() {
+    ///     println!("updated goldens: {report:?}");
+    ///     a.to_file("testdata/golden.txtar").unwrap();
+    /// }
+    /// ```
+    pub fn sync_from_dir(&mut self, dir: impl AsRef<Path>) -> io::Result<SyncReport> {
+        let disk = Archive::from_dir(dir)?;
+        let on_disk: HashMap<&str, &str> = disk
+            .iter()
+            .map(|f| (f.name.as_str(), f.content.as_str()))
+            .collect();
+        let mut report = SyncReport::default();
+
+        self.files.retain(|f| {
+            let keep = on_disk.contains_key(f.name.as_str());
+            if !keep {
+                report.removed.push(f.name.clone());
+            }
+            keep
+        });
+        for f in self.files.iter_mut() {
+            let content = on_disk[f.name.as_str()];
+            if f.content != content {
+                f.content = content.to_string();
+                report.modified.push(f.name.clone());
+            }
+        }
+
+        let existing: HashSet<&str> = self.files.iter().map(|f| f.name.as_str()).collect();
+        let added: Vec<File> = disk
+            .files
+            .into_iter()
+            .filter(|f| !existing.contains(f.name.as_str()))
+            .collect();
+
+        report.added = added.iter().map(|f| f.name.clone()).collect();
+        self.files.extend(added);
+
+        Ok(report)
+    }
+}
+
+impl Archive {
+    /// Compare the files in this archive with the files under the given directory without
+    /// modifying either of them.
+    ///
+    /// The directory is read in the same way as [Archive::from_dir] other than file content and
+    /// paths that are not valid UTF-8 being reported as differences rather than errors. Names in
+    /// the archive are listed in the order they appear, and names on disk in the order they are
+    /// found by [Archive::from_dir]. If the archive contains multiple files with the same name
+    /// then only the first is compared.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from_file("testdata/expected.txtar").unwrap();
+    /// let diff = a.diff_dir("target/generated").unwrap();
+    ///
+    /// assert!(diff.is_empty(), "generated output has drifted: {diff:#?}");
+    /// ```
+    pub fn diff_dir(&self, dir: impl AsRef<Path>) -> io::Result<DirDiff> {
+        Ok(self.diff_against(&ByteArchive::from_dir(dir)?))
+    }
+
+    /// Compare the files in this archive with the files read from a directory.
+    pub(crate) fn diff_against(&self, disk: &ByteArchive) -> DirDiff {
+        let on_disk: HashMap<&[u8], &[u8]> = disk
+            .iter()
+            .map(|f| (f.name.as_slice(), f.content.as_slice()))
+            .collect();
+        let mut diff = DirDiff::default();
+        let mut seen = HashSet::new();
+
+        for f in self.files.iter() {
+            if !seen.insert(f.name.as_str()) {
+                continue;
+            }
+            match on_disk.get(f.name.as_bytes()) {
+                Some(&content) if content != f.content.as_bytes() => {
+                    diff.content_differs.push(f.name.clone())
+                }
+                Some(_) => (),
+                None => diff.missing_on_disk.push(f.name.clone()),
+            }
+        }
+
+        diff.missing_in_archive = disk
+            .iter()
+            .map(|f| String::from_utf8_lossy(&f.name))
+            .filter(|name| !seen.contains(name.as_ref()))
+            .map(|name| name.into_owned())
+            .collect();
+
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn sync_only_touches_changed_files() {
+        let dir = TestDir::new("sync");
+        Archive::from("-- c --\nsame\n-- a --\nnew\n-- d --\nadded\n")
+            .materialize(&dir)
+            .unwrap();
+
+        let mut a = Archive::from("comment\n-- c --\nsame\n-- b --\ngone\n-- a --\nold\n");
+        let report = a.sync_from_dir(&dir);
+
+        assert_eq!(
+            report.unwrap(),
+            SyncReport {
+                added: vec!["d".to_string()],
+                modified: vec!["a".to_string()],
+                removed: vec!["b".to_string()],
+            }
+        );
+        assert_eq!(
+            a.to_string(),
+            "comment\n-- c --\nsame\n-- a --\nnew\n-- d --\nadded\n"
+        );
+    }
+
+    #[test]
+    fn diff_dir_reports_drift() {
+        let dir = TestDir::new("diff");
+        Archive::from("-- a --\nsame\n-- b --\nnew\n-- d/e --\nextra\n")
+            .materialize(&dir)
+            .unwrap();
+
+        let a = Archive::from("-- a --\nsame\n-- b --\nold\n-- c --\nmissing\n");
+        let diff = a.diff_dir(&dir);
+        let same = Archive::from_dir(&dir).unwrap().diff_dir(&dir);
+
+        assert_eq!(
+            diff.unwrap(),
+            DirDiff {
+                missing_on_disk: vec!["c".to_string()],
+                missing_in_archive: vec!["d/e".to_string()],
+                content_differs: vec!["b".to_string()],
+            }
+        );
+        assert!(same.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+//! Converting between [Archive]s and `tar` archives.
+use crate::{Archive, Binary, PackReport, SkipReason, Skipped};
+use std::{
+    io::{self, Read, Write},
+    path::PathBuf,
+};
+use tar::{Builder, EntryType, Header};
+
+impl Archive {
+    /// Write the files in this archive to `w` as a `tar` archive.
+    ///
+    /// Each file is written as a regular file with mode `0o644` and a modification time of zero,
+    /// so the output only depends on the contents of the archive. Parent directories are implied
+    /// by the file names rather than written as separate entries and the comment is not included.
+    /// This will error if any file name is not a valid relative path.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    ///
+    /// let a = Archive::from("-- a.txt --\nfoo\n-- nested/b.txt --\nbar\n");
+    /// let mut buf = Vec::new();
+    /// a.to_tar(&mut buf).unwrap();
+    ///
+    /// assert_eq!(Archive::from_tar(buf.as_slice()).unwrap(), a);
+    /// ```
+    pub fn to_tar(&self, w: impl Write) -> io::Result<()> {
+        let mut builder = Builder::new(w);
+        for f in self.files.iter() {
+            let mut header = Header::new_gnu();
+            header.set_entry_type(EntryType::Regular);
+            header.set_size(f.content.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(0);
+            builder.append_data(&mut header, &f.name, f.content.as_bytes())?;
+        }
+
+        builder.into_inner()?.flush()
+    }
+
+    /// Read the regular files from a `tar` archive into a new [Archive], in the order that they
+    /// appear.
+    ///
+    /// This will error if any file is not valid UTF-8. See [Archive::from_tar_with] for other
+    /// ways of handling binary files.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let f = std::fs::File::open("fixtures.tar").unwrap();
+    /// let a = Archive::from_tar(f).unwrap();
+    /// ```
+    pub fn from_tar(r: impl Read) -> io::Result<Self> {
+        Self::from_tar_with(r, Binary::Error).map(|(a, _)| a)
+    }
+
+    /// Read the regular files from a `tar` archive into a new [Archive], handling files that are
+    /// not valid UTF-8 according to the given [Binary] policy.
+    ///
+    /// Directory entries are ignored and symbolic or hard links are recorded in the returned
+    /// [PackReport] with [SkipReason::Symlink], along with any binary files that were skipped.
+    /// Other special files such as devices and FIFOs are ignored. A leading `./` is removed from
+    /// file names, and this will error if the name of a file is not valid UTF-8.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::{Archive, Binary};
+    ///
+    /// let f = std::fs::File::open("fixtures.tar").unwrap();
+    /// let (a, report) = Archive::from_tar_with(f, Binary::Skip).unwrap();
+    ///
+    /// for s in report.skipped.iter() {
+    ///     println!("skipped {:?}: {:?}", s.path, s.reason);
+    /// }
+    /// ```
+    pub fn from_tar_with(r: impl Read, binary: Binary) -> io::Result<(Self, PackReport)> {
+        let mut a = Archive::default();
+        let mut report = PackReport::default();
+
+        for entry in tar::Archive::new(r).entries()? {
+            let mut entry = entry?;
+            let name = String::from_utf8(entry.path_bytes().into_owned()).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("file name is not valid UTF-8: {:?}", e.as_bytes()),
+                )
+            })?;
+            let name = name.strip_prefix("./").unwrap_or(&name).to_string();
+            let path = PathBuf::from(&name);
+
+            match entry.header().entry_type() {
+                EntryType::Regular | EntryType::Continuous => (),
+                EntryType::Symlink | EntryType::Link => {
+                    report.skipped.push(Skipped {
+                        path,
+                        reason: SkipReason::Symlink,
+                    });
+                    continue;
+                }
+                _ => continue,
+            }
+
+            let mut bytes = Vec::with_capacity(entry.size() as usize);
+            entry.read_to_end(&mut bytes)?;
+            match binary.file(name, bytes, &path)? {
+                Some(file) => a.files.push(file),
+                None => report.skipped.push(Skipped {
+                    path,
+                    reason: SkipReason::Binary,
+                }),
+            }
+        }
+
+        Ok((a, report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_tar_applies_binary_policy() {
+        let mut builder = Builder::new(Vec::new());
+        for (name, data, kind) in [
+            ("./a.txt", &b"foo\n"[..], EntryType::Regular),
+            ("dir/", &b""[..], EntryType::Directory),
+            ("dir/bin", &[0xff, 0xfe][..], EntryType::Regular),
+        ] {
+            let mut header = Header::new_gnu();
+            header.set_entry_type(kind);
+            header.set_size(data.len() as u64);
+            builder.append_data(&mut header, name, data).unwrap();
+        }
+        let mut header = Header::new_gnu();
+        header.set_entry_type(EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, "link", "a.txt").unwrap();
+        let buf = builder.into_inner().unwrap();
+
+        let (a, report) = Archive::from_tar_with(buf.as_slice(), Binary::Skip).unwrap();
+
+        assert_eq!(a.to_string(), "-- a.txt --\nfoo\n");
+        assert_eq!(
+            report.skipped,
+            vec![
+                Skipped {
+                    path: "dir/bin".into(),
+                    reason: SkipReason::Binary
+                },
+                Skipped {
+                    path: "link".into(),
+                    reason: SkipReason::Symlink
+                },
+            ]
+        );
+        assert!(Archive::from_tar(buf.as_slice()).is_err());
+    }
+}
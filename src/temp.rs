@@ -0,0 +1,112 @@
+//! Extracting an [Archive] into a temporary directory that is removed when it is dropped.
+use crate::{Archive, Error, ExtractOptions, ExtractReport};
+use std::{
+    io,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+use tempfile::TempDir;
+
+/// A temporary directory containing the files of an archive, created by
+/// [Archive::materialize_temp].
+///
+/// The directory and everything inside of it is removed when this is dropped. It dereferences
+/// to the [Path] of the directory so it can be passed directly to anything expecting a path.
+#[derive(Debug)]
+pub struct TempDirHandle {
+    dir: TempDir,
+    report: ExtractReport,
+}
+
+impl TempDirHandle {
+    /// The path of the temporary directory.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// A summary of the files that were written into the directory.
+    pub fn report(&self) -> &ExtractReport {
+        &self.report
+    }
+
+    /// Keep the directory on disk rather than removing it when dropped, returning its path.
+    ///
+    /// This can be useful for inspecting the state of a fixture after a failing test.
+    pub fn keep(self) -> PathBuf {
+        self.dir.keep()
+    }
+
+    /// Remove the directory, returning any error encountered rather than ignoring it as happens
+    /// when this is dropped.
+    pub fn close(self) -> io::Result<()> {
+        self.dir.close()
+    }
+}
+
+impl Deref for TempDirHandle {
+    type Target = Path;
+
+    fn deref(&self) -> &Self::Target {
+        self.path()
+    }
+}
+
+impl AsRef<Path> for TempDirHandle {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl Archive {
+    /// Write each [File][crate::File] in this archive into a new temporary directory, which is
+    /// removed again when the returned [TempDirHandle] is dropped.
+    ///
+    /// Files are written in the same way as [Archive::materialize]. The directory is created
+    /// inside of [std::env::temp_dir] and is removed if extraction fails.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    /// use std::fs;
+    ///
+    /// let a = Archive::from("-- config.toml --\nname = \"test\"\n");
+    /// let dir = a.materialize_temp().unwrap();
+    ///
+    /// let config = fs::read_to_string(dir.join("config.toml")).unwrap();
+    /// assert_eq!(config, "name = \"test\"\n");
+    /// ```
+    pub fn materialize_temp(&self) -> Result<TempDirHandle, Error> {
+        self.materialize_temp_with(&ExtractOptions::default())
+    }
+
+    /// Write each [File][crate::File] in this archive into a new temporary directory using the
+    /// provided [ExtractOptions].
+    ///
+    /// See [Archive::materialize_temp] for details. As the directory is always new, the
+    /// [ExtractOptions::atomic] and conflict options have no effect.
+    pub fn materialize_temp_with(&self, opts: &ExtractOptions) -> Result<TempDirHandle, Error> {
+        let dir = tempfile::Builder::new().prefix("simple_txtar-").tempdir()?;
+        let mut opts = opts.clone();
+        opts.atomic(false);
+        let report = self.materialize_with(dir.path(), &opts)?;
+
+        Ok(TempDirHandle { dir, report })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directory_is_removed_on_drop() {
+        let a = Archive::from("-- a/b.txt --\nfoo\n");
+        let dir = a.materialize_temp().unwrap();
+        let path = dir.to_path_buf();
+
+        assert_eq!(Archive::from_dir(&dir).unwrap(), a);
+        assert_eq!(dir.report().files_written, 1);
+        drop(dir);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,44 @@
+//! Temporary directories for tests that are removed once the test has finished.
+use std::{
+    env, fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// An empty directory under the system temporary directory that is removed when dropped, so
+/// nothing is left behind when an assertion fails part way through a test.
+///
+/// The directory is named after the current process and `name`, which must be unique across the
+/// tests in this crate. Tests that need a path that does not exist yet should use one inside it.
+pub(crate) struct TestDir(PathBuf);
+
+impl TestDir {
+    pub(crate) fn new(name: &str) -> Self {
+        let dir = env::temp_dir().join(format!("simple_txtar-{}-{name}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        Self(dir)
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TestDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,128 @@
+//! Bindings for reading and constructing archives from JavaScript using [wasm_bindgen].
+//!
+//! The crate is only built as an `rlib` by default, so the module for `wasm-bindgen` to process
+//! needs to be built as a `cdylib` explicitly:
+//!
+//! ```sh
+//! cargo rustc --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/simple_txtar.wasm
+//! ```
+use crate::Archive;
+use wasm_bindgen::prelude::*;
+
+/// An [Archive] exported to JavaScript as the `Archive` class.
+///
+/// Archives are parsed and formatted by the same code as the rest of this crate, so fixtures
+/// built in the browser match those read by Rust tooling exactly.
+///
+/// ## Example
+/// ```js
+/// import { Archive } from "simple_txtar";
+///
+/// const a = Archive.parse("comment\n-- a.txt --\nfoo\n");
+/// a.set("b.txt", "bar\n");
+///
+/// console.log(a.names()); // ["a.txt", "b.txt"]
+/// console.log(a.get("a.txt")); // "foo\n"
+/// console.log(a.toString());
+/// ```
+#[wasm_bindgen(js_name = Archive)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct JsArchive {
+    inner: Archive,
+}
+
+#[wasm_bindgen(js_class = Archive)]
+impl JsArchive {
+    /// Construct a new, empty archive.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse an archive in the same way as [Archive::from].
+    pub fn parse(s: &str) -> Self {
+        Self {
+            inner: Archive::from(s),
+        }
+    }
+
+    /// Format the archive as `txtar` text in the same way as its [Display][std::fmt::Display]
+    /// implementation.
+    #[wasm_bindgen(js_name = toString)]
+    pub fn format(&self) -> String {
+        self.inner.to_string()
+    }
+
+    /// The comment at the top of the archive.
+    #[wasm_bindgen(getter)]
+    pub fn comment(&self) -> String {
+        self.inner.comment().to_string()
+    }
+
+    /// Replace the comment at the top of the archive.
+    #[wasm_bindgen(setter)]
+    pub fn set_comment(&mut self, comment: String) {
+        self.inner.set_comment(comment);
+    }
+
+    /// The number of files in the archive.
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// The names of the files in the archive, in order.
+    pub fn names(&self) -> Vec<String> {
+        self.inner.iter().map(|f| f.name.clone()).collect()
+    }
+
+    /// The content of the first file with the given name, or `undefined` if there is no such
+    /// file.
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.inner.get(name).map(|f| f.content.clone())
+    }
+
+    /// Replace the content of the first file with the given name, adding a new file to the end
+    /// of the archive if there is no such file.
+    pub fn set(&mut self, name: &str, content: String) {
+        self.inner.upsert(name, content);
+    }
+
+    /// Remove the first file with the given name, returning its content or `undefined` if there
+    /// is no such file.
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.inner.remove(name).map(|f| f.content)
+    }
+}
+
+impl From<Archive> for JsArchive {
+    fn from(inner: Archive) -> Self {
+        Self { inner }
+    }
+}
+
+impl From<JsArchive> for Archive {
+    fn from(a: JsArchive) -> Self {
+        a.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_match_archive_semantics() {
+        let mut a = JsArchive::parse("comment\n-- a.txt --\nfoo");
+        a.set("b.txt", "bar\n".to_string());
+        a.set("a.txt", "baz\n".to_string());
+        a.set_comment("new comment\n".to_string());
+
+        assert_eq!(a.names(), ["a.txt", "b.txt"]);
+        assert_eq!(a.get("a.txt").as_deref(), Some("baz\n"));
+        assert_eq!(a.remove("b.txt").as_deref(), Some("bar\n"));
+        assert_eq!(a.length(), 1);
+        assert_eq!(a.format(), "new comment\n-- a.txt --\nbaz\n");
+    }
+}
@@ -0,0 +1,119 @@
+//! Re-reading a directory into an [Archive] whenever its contents change.
+use crate::{Archive, PackOptions};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    fmt, io,
+    path::Path,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+/// How long to wait for further changes before re-reading the directory, so that a burst of
+/// changes such as an editor saving a file only results in a single update.
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// A handle to a directory being watched by [Archive::watch_dir].
+///
+/// The directory is watched until this is dropped.
+pub struct DirWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl fmt::Debug for DirWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DirWatcher").finish_non_exhaustive()
+    }
+}
+
+impl Archive {
+    /// Watch the given directory for changes, reading it into a new [Archive] using the provided
+    /// [PackOptions] and passing the result to `f` each time that it changes.
+    ///
+    /// `f` is called on a background thread: once with the initial contents of the directory and
+    /// then again after each change, with changes that happen in quick succession being grouped
+    /// together. Watching stops when the returned [DirWatcher] is dropped. Writing the archive to
+    /// a file inside of the watched directory will trigger another update so the output should
+    /// be written elsewhere.
+    ///
+    /// This will error if the directory can not be watched.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::{Archive, PackOptions};
+    ///
+    /// let _watcher = Archive::watch_dir("fixtures/basic", &PackOptions::new(), |res| match res {
+    ///     Ok(a) => a.to_file("testdata/basic.txtar").unwrap(),
+    ///     Err(e) => eprintln!("unable to read fixtures: {e}"),
+    /// })
+    /// .unwrap();
+    ///
+    /// std::thread::park();
+    /// ```
+    pub fn watch_dir(
+        dir: impl AsRef<Path>,
+        opts: &PackOptions,
+        mut f: impl FnMut(io::Result<Archive>) + Send + 'static,
+    ) -> io::Result<DirWatcher> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            // Reading the directory generates access events of its own which must be ignored.
+            if res.is_ok_and(|e| !matches!(e.kind, EventKind::Access(_))) {
+                let _ = tx.send(());
+            }
+        })
+        .map_err(io::Error::other)?;
+        watcher
+            .watch(dir.as_ref(), RecursiveMode::Recursive)
+            .map_err(io::Error::other)?;
+
+        let dir = dir.as_ref().to_path_buf();
+        let opts = opts.clone();
+        let read = move || Archive::from_dir_with(&dir, &opts).map(|(a, _)| a);
+
+        thread::spawn(move || {
+            f(read());
+            // The sender is dropped along with the watcher, disconnecting the channel.
+            while rx.recv().is_ok() {
+                loop {
+                    match rx.recv_timeout(DEBOUNCE) {
+                        Ok(()) => continue,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                f(read());
+            }
+        });
+
+        Ok(DirWatcher { _watcher: watcher })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+    use std::fs;
+
+    #[test]
+    fn changes_trigger_updates() {
+        let dir = TestDir::new("watch");
+        fs::write(dir.join("a.txt"), "foo\n").unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let watcher = Archive::watch_dir(&dir, &PackOptions::new(), move |res| {
+            let _ = tx.send(res.unwrap().to_string());
+        })
+        .unwrap();
+
+        let timeout = Duration::from_secs(5);
+        let initial = rx.recv_timeout(timeout);
+        fs::write(dir.join("b.txt"), "bar\n").unwrap();
+        let updated = rx.recv_timeout(timeout);
+        drop(watcher);
+
+        assert_eq!(initial.unwrap(), "-- a.txt --\nfoo\n");
+        assert_eq!(updated.unwrap(), "-- a.txt --\nfoo\n-- b.txt --\nbar\n");
+    }
+}
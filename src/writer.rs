@@ -0,0 +1,312 @@
+//! Writing `txtar` archives to files and streams.
+use crate::{extract::temp_sibling, Archive, File, MARKER, MARKER_END};
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// Configuration for writing an [Archive] to a file using [Archive::to_file_with].
+///
+/// The default options match the behaviour of [Archive::to_file].
+///
+/// ## Example
+/// ```no_run
+/// use simple_txtar::{Archive, WriteOptions};
+///
+/// let a = Archive::from("-- golden.txt --\nexpected output\n");
+/// a.to_file_with("testdata/golden.txtar", WriteOptions::new().atomic(true).sync(true))
+///     .unwrap();
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WriteOptions {
+    atomic: bool,
+    sync: bool,
+}
+
+impl WriteOptions {
+    /// Construct a new [WriteOptions] with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write the archive to a temporary file alongside the target path and then rename it into
+    /// place, so that readers only ever see either the previous or the new contents of the file.
+    ///
+    /// The permissions of an existing file are preserved. Defaults to `false`.
+    pub fn atomic(&mut self, atomic: bool) -> &mut Self {
+        self.atomic = atomic;
+        self
+    }
+
+    /// Flush the written file to disk before returning, along with its parent directory on unix
+    /// platforms when combined with [WriteOptions::atomic], so that the new contents survive a
+    /// crash. Defaults to `false`.
+    pub fn sync(&mut self, sync: bool) -> &mut Self {
+        self.sync = sync;
+        self
+    }
+}
+
+impl Archive {
+    /// Serialize this archive as a `txtar` file at the specified path using the provided
+    /// [WriteOptions].
+    ///
+    /// The output is identical to that of [Archive::to_file]. When writing atomically the
+    /// temporary file is removed again if anything fails, leaving any existing file untouched.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::{Archive, WriteOptions};
+    ///
+    /// let a = Archive::from("-- file1 --\nfoo");
+    /// a.to_file_with("my_txtar_archive", WriteOptions::new().atomic(true))
+    ///     .unwrap();
+    /// ```
+    pub fn to_file_with(&self, path: impl AsRef<Path>, opts: &WriteOptions) -> io::Result<()> {
+        let path = path.as_ref();
+        if !opts.atomic {
+            return self.write_file_to(path, opts.sync);
+        }
+
+        let tmp = temp_sibling(path)?;
+        let res = self.write_file_to(&tmp, opts.sync).and_then(|_| {
+            if let Ok(meta) = fs::metadata(path) {
+                fs::set_permissions(&tmp, meta.permissions())?;
+            }
+            fs::rename(&tmp, path)?;
+            if opts.sync {
+                sync_parent_dir(path)?;
+            }
+
+            Ok(())
+        });
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+
+        res
+    }
+
+    fn write_file_to(&self, path: &Path, sync: bool) -> io::Result<()> {
+        let mut w = io::BufWriter::new(fs::File::create(path)?);
+        self.to_writer(&mut w)?;
+        let f = w.into_inner().map_err(|e| e.into_error())?;
+        if sync {
+            f.sync_all()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => fs::File::open(dir)?.sync_all(),
+        _ => fs::File::open(".")?.sync_all(),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// A writer for streaming a `txtar` archive directly to an underlying [Write] implementation.
+///
+/// Anything written before the first call to [ArchiveWriter::start_file] forms the comment of the
+/// archive, and anything written after it forms the content of the most recently started file.
+/// Missing trailing newlines are inserted before each file marker and by
+/// [ArchiveWriter::finish], so the output is the same as the [Display][std::fmt::Display]
+/// implementation of the equivalent [Archive].
+///
+/// As with the rest of the crate, content is written as is: use [quote][crate::quote] for content
+/// that may contain lines that look like file markers.
+///
+/// ## Example
+/// ```rust
+/// use simple_txtar::{Archive, ArchiveWriter};
+/// use std::io::Write;
+///
+/// let mut w = ArchiveWriter::new(Vec::new());
+/// w.write_comment("recorded fixtures").unwrap();
+/// for i in 0..2 {
+///     w.start_file(&format!("{i}.txt")).unwrap();
+///     write!(w, "file {i}").unwrap();
+/// }
+/// let buf = w.finish().unwrap();
+///
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     "recorded fixtures\n-- 0.txt --\nfile 0\n-- 1.txt --\nfile 1\n"
+/// );
+/// ```
+#[derive(Debug)]
+pub struct ArchiveWriter<W: Write> {
+    inner: W,
+    in_file: bool,
+    needs_newline: bool,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    /// Create a new [ArchiveWriter] that writes to the given writer.
+    ///
+    /// Output is written directly to `inner` so wrapping it in an [io::BufWriter] is recommended
+    /// when writing many small chunks to a file.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            in_file: false,
+            needs_newline: false,
+        }
+    }
+
+    /// Append to the comment of the archive.
+    ///
+    /// This will error without writing anything if a file has already been started.
+    pub fn write_comment(&mut self, comment: &str) -> io::Result<()> {
+        if self.in_file {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the comment must be written before any files",
+            ));
+        }
+
+        self.write_all(comment.as_bytes())
+    }
+
+    /// Start a new file with the given name, ending the comment or previous file.
+    ///
+    /// This will error without writing anything if the name contains a newline, as it would not
+    /// be possible to parse the resulting file marker.
+    pub fn start_file(&mut self, name: &str) -> io::Result<()> {
+        if name.contains('\n') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("file name contains a newline: {name:?}"),
+            ));
+        }
+
+        self.end_segment()?;
+        writeln!(self.inner, "{MARKER}{name}{MARKER_END}")?;
+        self.in_file = true;
+
+        Ok(())
+    }
+
+    /// Write a complete [File] to the archive.
+    pub fn write_file(&mut self, file: &File) -> io::Result<()> {
+        self.start_file(&file.name)?;
+        self.write_all(file.content.as_bytes())
+    }
+
+    /// Write the comment and every file from an existing [Archive].
+    ///
+    /// An empty comment is skipped, so archives without a comment can be appended after files
+    /// that have already been written. This will error without writing anything if the archive
+    /// has a comment and a file has already been started.
+    pub fn write_archive(&mut self, archive: &Archive) -> io::Result<()> {
+        let comment = archive.comment_without_bom();
+        if !comment.is_empty() {
+            self.write_comment(comment)?;
+        }
+        archive.iter().try_for_each(|f| self.write_file(f))
+    }
+
+    /// Finish writing the archive, adding a trailing newline to the final file if needed and
+    /// returning the underlying writer after flushing it.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.end_segment()?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+
+    fn end_segment(&mut self) -> io::Result<()> {
+        if self.needs_newline {
+            self.inner.write_all(b"\n")?;
+            self.needs_newline = false;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes are appended to the comment if no file has been started yet, or to the content of the
+/// current file.
+impl<W: Write> Write for ArchiveWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if n > 0 {
+            self.needs_newline = buf[n - 1] != b'\n';
+        }
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn output_matches_display() {
+        let a = Archive::from("\u{feff}comment\n-- a --\n-- b --\nfoo\n-- c --\nbar");
+        let mut w = ArchiveWriter::new(Vec::new());
+
+        w.write_archive(&a).unwrap();
+        let buf = w.finish().unwrap();
+
+        assert_eq!(String::from_utf8(buf).unwrap(), a.to_string());
+    }
+
+    #[test]
+    fn atomic_writes_replace_the_file() {
+        let dir = TestDir::new("atomic-write");
+        let path = dir.join("a.txtar");
+        let a = Archive::from("-- a --\nfoo\n");
+        let b = Archive::from("-- a --\nbar\n");
+
+        a.to_file(&path).unwrap();
+        b.to_file_with(&path, WriteOptions::new().atomic(true).sync(true))
+            .unwrap();
+        let res = Archive::from_file_with(&path, &Default::default());
+        let names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+
+        assert_eq!(res.unwrap(), b);
+        assert_eq!(names, ["a.txtar"]);
+    }
+
+    #[test]
+    fn archives_without_a_comment_can_be_appended() {
+        let mut w = ArchiveWriter::new(Vec::new());
+        w.write_archive(&Archive::from("comment\n-- a --\nfoo"))
+            .unwrap();
+        w.write_archive(&Archive::from("-- b --\nbar")).unwrap();
+
+        assert!(w.write_archive(&Archive::from("late\n-- c --\n")).is_err());
+        assert_eq!(
+            String::from_utf8(w.finish().unwrap()).unwrap(),
+            "comment\n-- a --\nfoo\n-- b --\nbar\n"
+        );
+    }
+
+    #[test]
+    fn comment_after_file_is_an_error() {
+        let mut w = ArchiveWriter::new(Vec::new());
+        w.start_file("a").unwrap();
+
+        assert!(w.write_comment("too late").is_err());
+        assert!(w.start_file("b\nc").is_err());
+        assert_eq!(w.finish().unwrap(), b"-- a --\n");
+    }
+}
@@ -0,0 +1,150 @@
+//! Converting between [Archive]s and `zip` archives.
+use crate::{Archive, Binary, PackReport, SkipReason, Skipped};
+use std::{
+    io::{self, Read, Seek, Write},
+    path::PathBuf,
+};
+use zip::{write::SimpleFileOptions, CompressionMethod, DateTime, ZipArchive, ZipWriter};
+
+impl Archive {
+    /// Write the files in this archive to `w` as a `zip` archive.
+    ///
+    /// Each file is compressed using deflate and stored with mode `0o644` and the earliest
+    /// modification time that `zip` supports, so the output only depends on the contents of the
+    /// archive. Parent directories are implied by the file names rather than written as separate
+    /// entries and the comment is not included. This will error if the archive contains multiple
+    /// files with the same name.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use simple_txtar::Archive;
+    /// use std::io::Cursor;
+    ///
+    /// let a = Archive::from("-- a.txt --\nfoo\n-- nested/b.txt --\nbar\n");
+    /// let mut buf = Cursor::new(Vec::new());
+    /// a.to_zip(&mut buf).unwrap();
+    ///
+    /// assert_eq!(Archive::from_zip(buf).unwrap(), a);
+    /// ```
+    pub fn to_zip(&self, w: impl Write + Seek) -> io::Result<()> {
+        let opts = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .last_modified_time(DateTime::default())
+            .unix_permissions(0o644);
+        let mut z = ZipWriter::new(w);
+        for f in self.files.iter() {
+            z.start_file(f.name.as_str(), opts)?;
+            z.write_all(f.content.as_bytes())?;
+        }
+
+        z.finish()?.flush()
+    }
+
+    /// Read the regular files from a `zip` archive into a new [Archive], in the order that they
+    /// are stored.
+    ///
+    /// This will error if any file is not valid UTF-8. See [Archive::from_zip_with] for other
+    /// ways of handling binary files.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::Archive;
+    ///
+    /// let f = std::fs::File::open("fixtures.zip").unwrap();
+    /// let a = Archive::from_zip(f).unwrap();
+    /// ```
+    pub fn from_zip(r: impl Read + Seek) -> io::Result<Self> {
+        Self::from_zip_with(r, Binary::Error).map(|(a, _)| a)
+    }
+
+    /// Read the regular files from a `zip` archive into a new [Archive], handling files that are
+    /// not valid UTF-8 according to the given [Binary] policy.
+    ///
+    /// Directory entries are ignored and symbolic links are recorded in the returned
+    /// [PackReport] with [SkipReason::Symlink], along with any binary files that were skipped.
+    /// A leading `./` is removed from file names.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use simple_txtar::{Archive, Binary};
+    ///
+    /// let f = std::fs::File::open("fixtures.zip").unwrap();
+    /// let (a, report) = Archive::from_zip_with(f, Binary::Skip).unwrap();
+    ///
+    /// for s in report.skipped.iter() {
+    ///     println!("skipped {:?}: {:?}", s.path, s.reason);
+    /// }
+    /// ```
+    pub fn from_zip_with(r: impl Read + Seek, binary: Binary) -> io::Result<(Self, PackReport)> {
+        let mut z = ZipArchive::new(r)?;
+        let mut a = Archive::with_capacity(z.len());
+        let mut report = PackReport::default();
+
+        for i in 0..z.len() {
+            let mut entry = z.by_index(i)?;
+            if entry.is_dir() {
+                continue;
+            }
+            let name = entry.name();
+            let name = name.strip_prefix("./").unwrap_or(name).to_string();
+            let path = PathBuf::from(&name);
+
+            if entry.is_symlink() {
+                report.skipped.push(Skipped {
+                    path,
+                    reason: SkipReason::Symlink,
+                });
+                continue;
+            }
+
+            let mut bytes = Vec::with_capacity(entry.size() as usize);
+            entry.read_to_end(&mut bytes)?;
+            match binary.file(name, bytes, &path)? {
+                Some(file) => a.files.push(file),
+                None => report.skipped.push(Skipped {
+                    path,
+                    reason: SkipReason::Binary,
+                }),
+            }
+        }
+
+        Ok((a, report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn from_zip_applies_binary_policy() {
+        let opts = SimpleFileOptions::default();
+        let mut z = ZipWriter::new(Cursor::new(Vec::new()));
+        z.start_file("./a.txt", opts).unwrap();
+        z.write_all(b"foo\n").unwrap();
+        z.add_directory("dir/", opts).unwrap();
+        z.start_file("dir/bin", opts).unwrap();
+        z.write_all(&[0xff, 0xfe]).unwrap();
+        z.add_symlink("link", "a.txt", opts).unwrap();
+        let buf = z.finish().unwrap().into_inner();
+
+        let (a, report) = Archive::from_zip_with(Cursor::new(&buf), Binary::Skip).unwrap();
+
+        assert_eq!(a.to_string(), "-- a.txt --\nfoo\n");
+        assert_eq!(
+            report.skipped,
+            vec![
+                Skipped {
+                    path: "dir/bin".into(),
+                    reason: SkipReason::Binary
+                },
+                Skipped {
+                    path: "link".into(),
+                    reason: SkipReason::Symlink
+                },
+            ]
+        );
+        assert!(Archive::from_zip(Cursor::new(&buf)).is_err());
+    }
+}